@@ -0,0 +1,63 @@
+// parses the share fixtures of the scraper crate, "universe" parses as many
+// pages as there are listed shares, the same work as a full refresh; the
+// fixtures are hand-written, smaller than the real pages, so the timings are a
+// lower bound until they're replaced with save_live_fixtures
+//
+// cargo bench --bench parse_shares [--features streaming]
+
+use std::{fs, path::Path};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use scraper::{isins::types::ShareIsin, shares::parse_document};
+
+// roughly the number of shares on Euronext Milan
+const UNIVERSE_SIZE: usize = 420;
+
+fn share_fixtures() -> Vec<(ShareIsin, String)> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("scraper/tests/fixtures/shares");
+    let mut pages: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "html"))
+        .collect();
+    pages.sort();
+
+    pages
+        .into_iter()
+        .map(|page| {
+            let stem = page.file_stem().unwrap().to_string_lossy().to_string();
+            let isin = stem.split('_').next().unwrap().to_owned();
+            let share_isin = ShareIsin::new(stem, isin).unwrap();
+            (share_isin, fs::read_to_string(&page).unwrap())
+        })
+        .collect()
+}
+
+fn parse_shares(c: &mut Criterion) {
+    let fixtures = share_fixtures();
+
+    let mut group = c.benchmark_group("parse_share_page");
+    for (share_isin, res_txt) in fixtures.iter() {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(&share_isin.share_name),
+            res_txt,
+            |b, res_txt| b.iter(|| parse_document(res_txt, share_isin)),
+        );
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("parse_universe");
+    group.throughput(Throughput::Elements(UNIVERSE_SIZE as u64));
+    group.sample_size(20);
+    group.bench_function("shares", |b| {
+        b.iter(|| {
+            for (share_isin, res_txt) in fixtures.iter().cycle().take(UNIVERSE_SIZE) {
+                parse_document(res_txt, share_isin);
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse_shares);
+criterion_main!(benches);
@@ -0,0 +1,44 @@
+-- the bars of the shares traded on $1 (an Italian date); the snapshots hold the
+-- running values of the day, so the last non null one (or the extreme) is taken,
+-- snapshots scraped before the next open still refer to the same day
+WITH day AS (
+    SELECT
+        $1::DATE AS trade_date,
+        $1::DATE::TIMESTAMP AT TIME ZONE 'Europe/Rome' AS starts_at,
+        ($1::DATE + 1)::TIMESTAMP AT TIME ZONE 'Europe/Rome' AS ends_at
+)
+INSERT INTO daily_bars (
+    isin, trade_date, open, high, low, close, volume, turnover, trades, finalized_at
+)
+SELECT
+    ps.isin,
+    day.trade_date,
+    (ARRAY_AGG(ps.apertura_odierna ORDER BY ps.scraped_at DESC)
+        FILTER (WHERE ps.apertura_odierna IS NOT NULL))[1],
+    MAX(ps.max_oggi),
+    MIN(ps.min_oggi),
+    COALESCE(
+        (ARRAY_AGG(ps.prezzo_ufficiale ORDER BY ps.scraped_at DESC)
+            FILTER (WHERE ps.prezzo_ufficiale IS NOT NULL
+                AND ps.data_prezzo_ufficiale = day.trade_date))[1],
+        (ARRAY_AGG(ps.prezzo_ultimo_contratto ORDER BY ps.scraped_at DESC)
+            FILTER (WHERE ps.prezzo_ultimo_contratto IS NOT NULL))[1]
+    ),
+    MAX(ps.quantita_totale),
+    MAX(ps.controvalore),
+    MAX(ps.numero_contratti),
+    NOW()
+FROM price_snapshots ps, day
+WHERE ps.data_ora_ultimo_contratto >= day.starts_at
+  AND ps.data_ora_ultimo_contratto < day.ends_at
+  AND ps.scraped_at >= day.starts_at
+GROUP BY ps.isin, day.trade_date
+ON CONFLICT (isin, trade_date) DO UPDATE SET
+open = EXCLUDED.open,
+high = EXCLUDED.high,
+low = EXCLUDED.low,
+close = EXCLUDED.close,
+volume = EXCLUDED.volume,
+turnover = EXCLUDED.turnover,
+trades = EXCLUDED.trades,
+finalized_at = EXCLUDED.finalized_at
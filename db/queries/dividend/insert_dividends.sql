@@ -0,0 +1,6 @@
+INSERT INTO dividends (isin, ex_date, payment_date, amount, dividend_type, updated_at)
+SELECT * FROM UNNEST($1::VARCHAR[], $2::DATE[], $3::DATE[], $4::NUMERIC[], $5::VARCHAR[], $6::TIMESTAMPTZ[])
+ON CONFLICT (isin, ex_date, dividend_type) DO UPDATE SET
+payment_date = COALESCE(EXCLUDED.payment_date, dividends.payment_date),
+amount = EXCLUDED.amount,
+updated_at = EXCLUDED.updated_at
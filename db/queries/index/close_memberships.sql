@@ -0,0 +1,5 @@
+UPDATE index_memberships
+SET valid_to = $3
+WHERE index_code = $1
+  AND valid_to IS NULL
+  AND isin <> ALL($2::VARCHAR[])
@@ -0,0 +1,8 @@
+INSERT INTO indices (code, name, valore, var_percentuale, var_assoluta, updated_at)
+VALUES ($1, $2, $3, $4, $5, $6)
+ON CONFLICT (code) DO UPDATE SET
+name = EXCLUDED.name,
+valore = COALESCE(EXCLUDED.valore, indices.valore),
+var_percentuale = COALESCE(EXCLUDED.var_percentuale, indices.var_percentuale),
+var_assoluta = COALESCE(EXCLUDED.var_assoluta, indices.var_assoluta),
+updated_at = EXCLUDED.updated_at
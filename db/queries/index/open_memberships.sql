@@ -0,0 +1,6 @@
+-- constituents missing from share_isins are skipped until the next ISIN scrape
+INSERT INTO index_memberships (index_code, isin, valid_from)
+SELECT $1, c.isin, $3
+FROM UNNEST($2::VARCHAR[]) AS c(isin)
+WHERE EXISTS (SELECT 1 FROM share_isins si WHERE si.isin = c.isin)
+ON CONFLICT DO NOTHING
@@ -0,0 +1,8 @@
+-- partitions of table $1 (partitioned on $2) for this month and the next $3
+SELECT COUNT(*) FILTER (WHERE created) AS "created!"
+FROM generate_series(0, $3::INT) ahead,
+LATERAL create_monthly_partition(
+    $1::TEXT,
+    $2::TEXT,
+    (date_trunc('month', NOW() AT TIME ZONE 'UTC') + make_interval(months => ahead))::DATE
+) created
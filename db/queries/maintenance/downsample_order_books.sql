@@ -0,0 +1,22 @@
+-- keeps the last book of each (Italian) day before the day $1 days ago,
+-- starting from the day the previous run stopped at
+WITH ranked AS (
+    SELECT
+        isin,
+        snapshot_at,
+        ROW_NUMBER() OVER (
+            PARTITION BY isin, (snapshot_at AT TIME ZONE 'Europe/Rome')::DATE
+            ORDER BY snapshot_at DESC
+        ) AS rank
+    FROM (SELECT DISTINCT isin, snapshot_at FROM order_book_levels) books
+    WHERE snapshot_at >= COALESCE(
+            (SELECT downsampled_until FROM snapshot_downsampling
+             WHERE table_name = 'order_book_levels')::TIMESTAMP AT TIME ZONE 'Europe/Rome',
+            '-infinity'
+        )
+        AND snapshot_at < ((NOW() AT TIME ZONE 'Europe/Rome')::DATE - $1::INT)::TIMESTAMP
+            AT TIME ZONE 'Europe/Rome'
+)
+DELETE FROM order_book_levels obl
+USING ranked
+WHERE obl.isin = ranked.isin AND obl.snapshot_at = ranked.snapshot_at AND ranked.rank > 1
@@ -0,0 +1,22 @@
+-- keeps the last snapshot of each (Italian) day before the day $1 days ago,
+-- starting from the day the previous run stopped at
+WITH ranked AS (
+    SELECT
+        isin,
+        scraped_at,
+        ROW_NUMBER() OVER (
+            PARTITION BY isin, (scraped_at AT TIME ZONE 'Europe/Rome')::DATE
+            ORDER BY scraped_at DESC
+        ) AS rank
+    FROM price_snapshots
+    WHERE scraped_at >= COALESCE(
+            (SELECT downsampled_until FROM snapshot_downsampling
+             WHERE table_name = 'price_snapshots')::TIMESTAMP AT TIME ZONE 'Europe/Rome',
+            '-infinity'
+        )
+        AND scraped_at < ((NOW() AT TIME ZONE 'Europe/Rome')::DATE - $1::INT)::TIMESTAMP
+            AT TIME ZONE 'Europe/Rome'
+)
+DELETE FROM price_snapshots ps
+USING ranked
+WHERE ps.isin = ranked.isin AND ps.scraped_at = ranked.scraped_at AND ranked.rank > 1
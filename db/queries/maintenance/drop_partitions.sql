@@ -0,0 +1,6 @@
+-- partitions of table $1 (partitioned on $2) older than $3 months, the current one excluded
+SELECT drop_monthly_partitions(
+    $1::TEXT,
+    $2::TEXT,
+    (date_trunc('month', NOW() AT TIME ZONE 'UTC') - make_interval(months => $3::INT))::DATE
+) AS "dropped!"
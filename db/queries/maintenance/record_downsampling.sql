@@ -0,0 +1,11 @@
+-- table $1 is downsampled up to the day $2 days ago, a run with a longer
+-- downsample_after_days doesn't move it back
+INSERT INTO snapshot_downsampling (table_name, downsampled_until, updated_at)
+VALUES ($1, (NOW() AT TIME ZONE 'Europe/Rome')::DATE - $2::INT, NOW())
+ON CONFLICT (table_name) DO UPDATE
+SET downsampled_until = GREATEST(
+        snapshot_downsampling.downsampled_until,
+        EXCLUDED.downsampled_until
+    ),
+    updated_at = EXCLUDED.updated_at
+RETURNING downsampled_until AS "downsampled_until!"
@@ -0,0 +1,7 @@
+INSERT INTO press_releases (isin, link, title, published_at, body, updated_at)
+SELECT * FROM UNNEST($1::VARCHAR[], $2::VARCHAR[], $3::TEXT[], $4::TIMESTAMPTZ[], $5::TEXT[], $6::TIMESTAMPTZ[])
+ON CONFLICT (isin, link) DO UPDATE SET
+title = EXCLUDED.title,
+published_at = EXCLUDED.published_at,
+body = COALESCE(EXCLUDED.body, press_releases.body),
+updated_at = EXCLUDED.updated_at
@@ -0,0 +1,13 @@
+INSERT INTO company_profiles (
+    isin, ragione_sociale, indirizzo, sito_web, descrizione,
+    azioni_in_circolazione, flottante, data_quotazione, updated_at
+) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+ON CONFLICT (isin) DO UPDATE SET
+ragione_sociale = COALESCE(EXCLUDED.ragione_sociale, company_profiles.ragione_sociale),
+indirizzo = COALESCE(EXCLUDED.indirizzo, company_profiles.indirizzo),
+sito_web = COALESCE(EXCLUDED.sito_web, company_profiles.sito_web),
+descrizione = COALESCE(EXCLUDED.descrizione, company_profiles.descrizione),
+azioni_in_circolazione = COALESCE(EXCLUDED.azioni_in_circolazione, company_profiles.azioni_in_circolazione),
+flottante = COALESCE(EXCLUDED.flottante, company_profiles.flottante),
+data_quotazione = COALESCE(EXCLUDED.data_quotazione, company_profiles.data_quotazione),
+updated_at = COALESCE(EXCLUDED.updated_at, company_profiles.updated_at)
@@ -0,0 +1,6 @@
+INSERT INTO share_details (isin, id_strumento, codice_alfanumerico, updated_at)
+SELECT * FROM UNNEST($1::VARCHAR[], $2::DOUBLE PRECISION[], $3::VARCHAR[], $4::TIMESTAMPTZ[])
+ON CONFLICT (isin) DO UPDATE SET
+id_strumento = COALESCE(EXCLUDED.id_strumento, share_details.id_strumento),
+codice_alfanumerico = COALESCE(EXCLUDED.codice_alfanumerico, share_details.codice_alfanumerico),
+updated_at = COALESCE(EXCLUDED.updated_at, share_details.updated_at)
@@ -0,0 +1,8 @@
+INSERT INTO market_information (isin, super_sector, mercato_segmento, capitalizzazione_di_mercato, lotto_minimo, updated_at)
+SELECT * FROM UNNEST($1::VARCHAR[], $2::VARCHAR[], $3::VARCHAR[], $4::NUMERIC[], $5::DOUBLE PRECISION[], $6::TIMESTAMPTZ[])
+ON CONFLICT (isin) DO UPDATE SET
+super_sector = COALESCE(EXCLUDED.super_sector, market_information.super_sector),
+mercato_segmento = COALESCE(EXCLUDED.mercato_segmento, market_information.mercato_segmento),
+capitalizzazione_di_mercato = COALESCE(EXCLUDED.capitalizzazione_di_mercato, market_information.capitalizzazione_di_mercato),
+lotto_minimo = COALESCE(EXCLUDED.lotto_minimo, market_information.lotto_minimo),
+updated_at = COALESCE(EXCLUDED.updated_at, market_information.updated_at)
@@ -0,0 +1,3 @@
+INSERT INTO market_phases (phase)
+SELECT DISTINCT * FROM UNNEST($1::VARCHAR[])
+ON CONFLICT DO NOTHING
@@ -0,0 +1,3 @@
+INSERT INTO market_segments (segment)
+SELECT DISTINCT * FROM UNNEST($1::VARCHAR[])
+ON CONFLICT DO NOTHING
@@ -0,0 +1,6 @@
+INSERT INTO order_book_levels (isin, snapshot_at, side, level, price, quantity, orders)
+SELECT * FROM UNNEST(
+    $1::VARCHAR[], $2::TIMESTAMPTZ[], $3::VARCHAR[], $4::SMALLINT[],
+    $5::NUMERIC[], $6::BIGINT[], $7::INT[]
+)
+ON CONFLICT DO NOTHING
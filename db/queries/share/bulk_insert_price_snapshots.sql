@@ -0,0 +1,17 @@
+INSERT INTO price_snapshots (
+    isin, fase_di_mercato, prezzo_ultimo_contratto, var_percentuale,
+    var_assoluta, pr_medio_progr, data_ora_ultimo_contratto, quantita_ultimo,
+    quantita_totale, numero_contratti, controvalore, max_oggi, max_anno,
+    max_anno_date, min_oggi, min_anno, min_anno_date, chiusura_precedente,
+    prezzo_riferimento, data_ora_prezzo_rifermento, prezzo_ufficiale,
+    data_prezzo_ufficiale, apertura_odierna, scraped_at
+)
+SELECT * FROM UNNEST(
+    $1::VARCHAR[], $2::VARCHAR[], $3::NUMERIC[], $4::NUMERIC[], $5::NUMERIC[],
+    $6::NUMERIC[], $7::TIMESTAMPTZ[], $8::NUMERIC[], $9::NUMERIC[], $10::INT[],
+    $11::NUMERIC[], $12::NUMERIC[], $13::NUMERIC[], $14::DATE[], $15::NUMERIC[],
+    $16::NUMERIC[], $17::DATE[], $18::NUMERIC[], $19::NUMERIC[], $20::TIMESTAMPTZ[],
+    $21::NUMERIC[], $22::DATE[], $23::NUMERIC[], $24::TIMESTAMPTZ[]
+)
+-- the same scrape inserted twice
+ON CONFLICT (isin, scraped_at) DO NOTHING
@@ -0,0 +1,3 @@
+INSERT INTO market_phases (phase)
+VALUES ($1)
+ON CONFLICT DO NOTHING
@@ -0,0 +1,3 @@
+INSERT INTO market_segments (segment)
+VALUES ($1)
+ON CONFLICT DO NOTHING
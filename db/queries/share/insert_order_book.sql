@@ -0,0 +1,5 @@
+INSERT INTO order_book_levels (isin, snapshot_at, side, level, price, quantity, orders)
+SELECT $1, $2, side, level, price, quantity, orders
+FROM UNNEST($3::VARCHAR[], $4::SMALLINT[], $5::NUMERIC[], $6::BIGINT[], $7::INT[])
+AS levels(side, level, price, quantity, orders)
+ON CONFLICT DO NOTHING
@@ -0,0 +1,14 @@
+INSERT INTO price_snapshots (
+    isin, fase_di_mercato, prezzo_ultimo_contratto, var_percentuale,
+    var_assoluta, pr_medio_progr, data_ora_ultimo_contratto, quantita_ultimo,
+    quantita_totale, numero_contratti, controvalore, max_oggi, max_anno,
+    max_anno_date, min_oggi, min_anno, min_anno_date, chiusura_precedente,
+    prezzo_riferimento, data_ora_prezzo_rifermento, prezzo_ufficiale,
+    data_prezzo_ufficiale, apertura_odierna, scraped_at
+) VALUES (
+    $1, $2, $3, $4, $5, $6, $7, $8, $9, $10,
+    $11, $12, $13, $14, $15, $16, $17, $18, $19,
+    $20, $21, $22, $23, $24
+)
+-- the same scrape inserted twice
+ON CONFLICT (isin, scraped_at) DO NOTHING
@@ -0,0 +1,3 @@
+INSERT INTO trades (isin, traded_at, price, quantity, seq)
+SELECT * FROM UNNEST($1::VARCHAR[], $2::TIMESTAMPTZ[], $3::NUMERIC[], $4::BIGINT[], $5::INT[])
+ON CONFLICT DO NOTHING
@@ -0,0 +1,183 @@
+use std::{env, fmt::Display, path::Path, str::FromStr, time::Duration};
+
+use serde::Deserialize;
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions, PgSslMode},
+    Pool, Postgres,
+};
+use tracing::{error, info, warn};
+
+use crate::{shares::DEFAULT_SHARES_BATCH_SIZE, utils::empty_string_as_none};
+
+// the settings of a file are overridden by the environment, see
+// db/config.example.toml for the variables
+const CONFIG_PATH_VAR: &str = "DATABASE_CONFIG";
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DbConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout_secs: u64,
+    // None keeps idle connections open
+    pub idle_timeout_secs: Option<u64>,
+    // None uses the server default
+    pub statement_timeout_ms: Option<u64>,
+    pub application_name: String,
+    // None uses the mode of the url, `prefer` if it has none
+    #[serde(deserialize_with = "empty_string_as_none")]
+    pub ssl_mode: Option<PgSslMode>,
+    // shares upserted per statement by insert_all_shares
+    pub shares_batch_size: usize,
+}
+
+#[derive(Debug)]
+pub enum DbConfigError {
+    Io(std::io::Error),
+    Toml(toml::de::Error),
+    MissingUrl,
+    InvalidVar { var: &'static str, value: String },
+}
+
+impl Display for DbConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DbConfigError::Io(e) => write!(f, "unable to read database config: {}", e),
+            DbConfigError::Toml(e) => write!(f, "invalid database config file: {}", e),
+            DbConfigError::MissingUrl => {
+                write!(
+                    f,
+                    "no database url, set DATABASE_URL or `url` in the config file"
+                )
+            }
+            DbConfigError::InvalidVar { var, value } => {
+                write!(f, "invalid value {:?} for {}", value, var)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DbConfigError {}
+
+impl Default for DbConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout_secs: 10,
+            idle_timeout_secs: Some(600),
+            statement_timeout_ms: None,
+            application_name: "share_service".to_owned(),
+            ssl_mode: None,
+            shares_batch_size: DEFAULT_SHARES_BATCH_SIZE,
+        }
+    }
+}
+
+impl DbConfig {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            ..Default::default()
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, DbConfigError> {
+        info!("Loading database config from {}", path.display());
+        let text = std::fs::read_to_string(path).map_err(DbConfigError::Io)?;
+        toml::from_str(&text).map_err(DbConfigError::Toml)
+    }
+
+    // loads .env, then the file in DATABASE_CONFIG if set, then the DATABASE_*
+    // variables
+    pub fn from_env() -> Result<Self, DbConfigError> {
+        match dotenv::dotenv() {
+            Ok(_) => info!("Environment variables loaded successfully"),
+            Err(e) => warn!("Failed to load .env file: {}", e),
+        }
+
+        let mut config = match env::var_os(CONFIG_PATH_VAR) {
+            Some(path) => Self::from_file(Path::new(&path))?,
+            None => Self::default(),
+        };
+
+        if let Ok(url) = env::var("DATABASE_URL") {
+            config.url = url;
+        }
+        if let Some(max) = parse_var("DATABASE_MAX_CONNECTIONS")? {
+            config.max_connections = max;
+        }
+        if let Some(min) = parse_var("DATABASE_MIN_CONNECTIONS")? {
+            config.min_connections = min;
+        }
+        if let Some(secs) = parse_var("DATABASE_ACQUIRE_TIMEOUT_SECS")? {
+            config.acquire_timeout_secs = secs;
+        }
+        if let Some(secs) = parse_var("DATABASE_IDLE_TIMEOUT_SECS")? {
+            config.idle_timeout_secs = Some(secs);
+        }
+        if let Some(ms) = parse_var("DATABASE_STATEMENT_TIMEOUT_MS")? {
+            config.statement_timeout_ms = Some(ms);
+        }
+        if let Ok(name) = env::var("DATABASE_APPLICATION_NAME") {
+            config.application_name = name;
+        }
+        if let Some(mode) = parse_var("DATABASE_SSL_MODE")? {
+            config.ssl_mode = Some(mode);
+        }
+        if let Some(size) = parse_var("DATABASE_SHARES_BATCH_SIZE")? {
+            config.shares_batch_size = size;
+        }
+
+        Ok(config)
+    }
+
+    pub fn connect_options(&self) -> Result<PgConnectOptions, sqlx::Error> {
+        if self.url.is_empty() {
+            return Err(sqlx::Error::Configuration(DbConfigError::MissingUrl.into()));
+        }
+
+        let mut options =
+            PgConnectOptions::from_str(&self.url)?.application_name(&self.application_name);
+        if let Some(ssl_mode) = self.ssl_mode {
+            options = options.ssl_mode(ssl_mode);
+        }
+        if let Some(ms) = self.statement_timeout_ms {
+            options = options.options([("statement_timeout", ms.to_string())]);
+        }
+
+        Ok(options)
+    }
+
+    pub fn pool_options(&self) -> PgPoolOptions {
+        PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .min_connections(self.min_connections)
+            .acquire_timeout(Duration::from_secs(self.acquire_timeout_secs))
+            .idle_timeout(self.idle_timeout_secs.map(Duration::from_secs))
+    }
+
+    pub async fn connect(&self) -> Result<Pool<Postgres>, sqlx::Error> {
+        info!(
+            "Attempting to connecting to database with up to {} connections",
+            self.max_connections
+        );
+        self.pool_options()
+            .connect_with(self.connect_options()?)
+            .await
+            .inspect_err(|e| error!("Database connection failed: {}", e))
+    }
+}
+
+// empty variables are ignored
+fn parse_var<T: FromStr>(var: &'static str) -> Result<Option<T>, DbConfigError> {
+    match env::var(var) {
+        Ok(value) if !value.is_empty() => value
+            .parse()
+            .map(Some)
+            .map_err(|_| DbConfigError::InvalidVar { var, value }),
+        _ => Ok(None),
+    }
+}
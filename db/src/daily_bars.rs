@@ -0,0 +1,87 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{prelude::FromRow, query_as, query_file, Pool, Postgres};
+use tracing::{error, info};
+
+use crate::metrics::InsertionMetrics;
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct DailyBar {
+    pub isin: String,
+    pub trade_date: NaiveDate,
+    pub open: Option<Decimal>,
+    pub high: Option<Decimal>,
+    pub low: Option<Decimal>,
+    pub close: Option<Decimal>,
+    pub volume: Option<Decimal>,
+    pub turnover: Option<Decimal>,
+    pub trades: Option<i32>,
+    pub finalized_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DailyBarQuery {
+    pub isin: String,
+    // inclusive, as YYYY-MM-DD
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+// (re)builds the bars of every day in [from, to], one day at a time
+pub async fn build_all_daily_bars(
+    from: NaiveDate,
+    to: NaiveDate,
+    pool: &Pool<Postgres>,
+) -> InsertionMetrics {
+    let mut metrics = InsertionMetrics::default();
+
+    info!("Building daily bars from {} to {}", from, to);
+
+    for trade_date in from.iter_days().take_while(|date| *date <= to) {
+        metrics.total += 1;
+        match build_daily_bars(trade_date, pool).await {
+            Ok(bars) => {
+                info!("Built {} daily bars for {}", bars, trade_date);
+                metrics.successful += 1;
+            }
+            Err(e) => error!("Unable to build daily bars for {}, {}", trade_date, e),
+        }
+    }
+
+    metrics
+}
+
+// bars of the shares traded on `trade_date`, replacing the ones already built
+pub async fn build_daily_bars(
+    trade_date: NaiveDate,
+    pool: &Pool<Postgres>,
+) -> Result<u64, sqlx::Error> {
+    let res = query_file!("./queries/daily_bar/build_daily_bars.sql", trade_date)
+        .execute(pool)
+        .await?;
+
+    Ok(res.rows_affected())
+}
+
+pub async fn query_daily_bars(
+    query: &DailyBarQuery,
+    pool: &Pool<Postgres>,
+) -> Result<Vec<DailyBar>, sqlx::Error> {
+    info!(
+        "Querying daily bars for {} from {} to {}",
+        query.isin, query.from, query.to
+    );
+    query_as(
+        r#"
+        SELECT * FROM daily_bars
+        WHERE isin = $1 AND trade_date BETWEEN $2 AND $3
+        ORDER BY trade_date
+        "#,
+    )
+    .bind(&query.isin)
+    .bind(query.from)
+    .bind(query.to)
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,86 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use scraper::dividends::types::Dividend;
+use sqlx::{query_as, query_file, Pool, Postgres};
+use tracing::{error, info};
+
+use crate::metrics::InsertionMetrics;
+
+const DIVIDENDS_BATCH_SIZE: usize = 1000;
+
+pub async fn insert_all_dividends(
+    dividends: Vec<Dividend>,
+    pool: &Pool<Postgres>,
+) -> InsertionMetrics {
+    let dividend_num = dividends.len() as i32;
+    let mut successful_inserts = 0;
+
+    info!("Inserting a total of {} dividends", dividend_num);
+
+    for batch in dividends.chunks(DIVIDENDS_BATCH_SIZE) {
+        match insert_dividends(batch, pool).await {
+            Ok(_) => successful_inserts += batch.len() as i32,
+            Err(e) => error!("Unable to insert {} dividends, {}", batch.len(), e),
+        }
+    }
+
+    InsertionMetrics {
+        total: dividend_num,
+        successful: successful_inserts,
+        ..Default::default()
+    }
+}
+
+pub async fn insert_dividends(
+    dividends: &[Dividend],
+    pool: &Pool<Postgres>,
+) -> Result<u64, sqlx::Error> {
+    let isins: Vec<String> = dividends.iter().map(|d| d.isin.clone()).collect();
+    let ex_dates: Vec<NaiveDate> = dividends.iter().map(|d| d.ex_date).collect();
+    let payment_dates: Vec<Option<NaiveDate>> = dividends.iter().map(|d| d.payment_date).collect();
+    let amounts: Vec<_> = dividends.iter().map(|d| d.amount).collect();
+    let types: Vec<String> = dividends.iter().map(|d| d.dividend_type.clone()).collect();
+    let updated_ats: Vec<DateTime<Utc>> = dividends.iter().map(|d| d.updated_at).collect();
+
+    let res = query_file!(
+        "./queries/dividend/insert_dividends.sql",
+        &isins,
+        &ex_dates,
+        &payment_dates as &[Option<NaiveDate>],
+        &amounts,
+        &types,
+        &updated_ats
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(res.rows_affected())
+}
+
+pub async fn query_dividend_history(
+    isin: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Vec<Dividend>, sqlx::Error> {
+    info!("Querying dividend history for {}", isin);
+    query_as("SELECT * FROM dividends WHERE isin = $1 ORDER BY ex_date DESC")
+        .bind(isin)
+        .fetch_all(pool)
+        .await
+}
+
+// dividends not yet detached or not yet paid
+pub async fn query_upcoming_dividends(
+    isin: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Vec<Dividend>, sqlx::Error> {
+    info!("Querying upcoming dividends for {}", isin);
+    query_as(
+        r#"
+        SELECT * FROM dividends
+        WHERE isin = $1 AND (ex_date >= CURRENT_DATE OR payment_date >= CURRENT_DATE)
+        ORDER BY ex_date
+        "#,
+    )
+    .bind(isin)
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,123 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{prelude::FromRow, Pool, Postgres, QueryBuilder};
+use tracing::info;
+
+use crate::utils::{empty_string_as_none, push_condition};
+
+// logged by the triggers on share_details and market_information, see
+// migrations/*_field_changes.sql
+#[derive(Debug, Serialize, FromRow)]
+pub struct FieldChange {
+    pub isin: String,
+    pub table_name: String,
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub changed_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ChangeTimelineQuery {
+    pub isin: String,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub field: Option<String>,
+    // RFC 3339, `to` is exclusive
+    #[serde(default)]
+    pub from: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub to: Option<DateTime<Utc>>,
+}
+
+// oldest change first
+pub async fn query_change_timeline(
+    query: ChangeTimelineQuery,
+    pool: &Pool<Postgres>,
+) -> Result<Vec<FieldChange>, sqlx::Error> {
+    info!("Querying change timeline with {:?}", query);
+    let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(
+        "SELECT isin, table_name, field, old_value, new_value, changed_at FROM field_changes",
+    );
+    let mut has_conditions = false;
+
+    push_condition(&mut query_builder, &mut has_conditions);
+    query_builder.push("isin = ").push_bind(query.isin);
+
+    if let Some(field) = query.field {
+        push_condition(&mut query_builder, &mut has_conditions);
+        query_builder.push("field = ").push_bind(field);
+    }
+    if let Some(from) = query.from {
+        push_condition(&mut query_builder, &mut has_conditions);
+        query_builder.push("changed_at >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        push_condition(&mut query_builder, &mut has_conditions);
+        query_builder.push("changed_at < ").push_bind(to);
+    }
+    query_builder.push(" ORDER BY changed_at, id");
+
+    query_builder.build_query_as().fetch_all(pool).await
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::share_batches::{
+        insert_share_batch,
+        tests::{dec, insert_isins, now, test_share, ENEL},
+    };
+
+    async fn changes(pool: &PgPool) -> Vec<FieldChange> {
+        let query = ChangeTimelineQuery {
+            isin: ENEL.to_owned(),
+            field: None,
+            from: None,
+            to: None,
+        };
+        query_change_timeline(query, pool).await.unwrap()
+    }
+
+    #[sqlx::test(migrator = "crate::migrations::MIGRATOR")]
+    async fn logs_changed_reference_fields(pool: PgPool) {
+        insert_isins(&[ENEL], &pool).await;
+        let first_scrape = now();
+        insert_share_batch(&[test_share(ENEL, "6.868", first_scrape)], &pool)
+            .await
+            .unwrap();
+        assert!(changes(&pool).await.is_empty());
+
+        let second_scrape = first_scrape + Duration::minutes(5);
+        let mut share = test_share(ENEL, "6.9", second_scrape);
+        share.market_information.super_sector = Some("Energy".to_owned());
+        insert_share_batch(&[share], &pool).await.unwrap();
+
+        let changes = changes(&pool).await;
+        assert_eq!(changes.len(), 1, "{:?}", changes);
+        let change = &changes[0];
+        assert_eq!(change.table_name, "market_information");
+        assert_eq!(change.field, "super_sector");
+        assert_eq!(change.old_value.as_deref(), Some("Utilities"));
+        assert_eq!(change.new_value.as_deref(), Some("Energy"));
+        assert_eq!(change.changed_at, second_scrape);
+    }
+
+    #[sqlx::test(migrator = "crate::migrations::MIGRATOR")]
+    async fn skips_price_driven_fields(pool: PgPool) {
+        insert_isins(&[ENEL], &pool).await;
+        let first_scrape = now();
+        insert_share_batch(&[test_share(ENEL, "6.868", first_scrape)], &pool)
+            .await
+            .unwrap();
+
+        let mut share = test_share(ENEL, "6.9", first_scrape + Duration::minutes(5));
+        share.market_information.capitalizzazione_di_mercato = Some(dec("70150000000"));
+        share.performance_metrics.performance_1_mese = Some(dec("2.1"));
+        insert_share_batch(&[share], &pool).await.unwrap();
+
+        assert!(changes(&pool).await.is_empty());
+    }
+}
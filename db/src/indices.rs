@@ -0,0 +1,193 @@
+use chrono::Utc;
+use futures::{stream::FuturesUnordered, StreamExt};
+use scraper::indices::types::{IndexComposition, IndexLevel, IndexMembership};
+use sqlx::{query_as, query_file, Pool, Postgres};
+use tracing::{error, info, warn};
+
+use crate::metrics::InsertionMetrics;
+
+pub async fn insert_all_indices(
+    compositions: Vec<IndexComposition>,
+    pool: &Pool<Postgres>,
+) -> InsertionMetrics {
+    let index_num = compositions.len() as i32;
+    let mut tasks = FuturesUnordered::new();
+
+    info!("Inserting a total of {} indices", index_num);
+
+    for composition in compositions {
+        tasks.push(insert_index(composition, pool));
+    }
+
+    let mut successful_inserts = 0;
+    while let Some(res) = tasks.next().await {
+        if let Err(e) = res {
+            error!("Unable to insert index, {}", e);
+        } else {
+            successful_inserts += 1;
+        }
+    }
+
+    InsertionMetrics {
+        total: index_num,
+        successful: successful_inserts,
+        ..Default::default()
+    }
+}
+
+// memberships of shares no longer listed are closed and new constituents get
+// an open one, both at the same instant; an incomplete list only opens them
+pub async fn insert_index(
+    composition: IndexComposition,
+    pool: &Pool<Postgres>,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let level = composition.level;
+    let now = Utc::now();
+
+    info!("Inserting index {}", level.code);
+    query_file!(
+        "./queries/index/insert_index.sql",
+        level.code,
+        level.name,
+        level.valore,
+        level.var_percentuale,
+        level.var_assoluta,
+        level.updated_at
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // an empty list is a failed scrape, not an empty index
+    if composition.constituents.is_empty() {
+        warn!("No constituents for {}, keeping memberships", level.code);
+        return tx.commit().await;
+    }
+
+    // shares past the page limit aren't listed, their memberships stay open
+    let closed = if composition.complete {
+        query_file!(
+            "./queries/index/close_memberships.sql",
+            level.code,
+            &composition.constituents,
+            now
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+    } else {
+        warn!(
+            "Incomplete constituents of {}, not closing memberships",
+            level.code
+        );
+        0
+    };
+    let opened = query_file!(
+        "./queries/index/open_memberships.sql",
+        level.code,
+        &composition.constituents,
+        now
+    )
+    .execute(&mut *tx)
+    .await?;
+    info!(
+        "{} memberships of {} opened, {} closed",
+        opened.rows_affected(),
+        level.code,
+        closed
+    );
+
+    tx.commit().await
+}
+
+pub async fn query_all_indices(pool: &Pool<Postgres>) -> Result<Vec<IndexLevel>, sqlx::Error> {
+    info!("Querying all indices");
+    query_as("SELECT * FROM indices ORDER BY code")
+        .fetch_all(pool)
+        .await
+}
+
+// the full history, current constituents have no valid_to
+pub async fn query_index_memberships(
+    code: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Vec<IndexMembership>, sqlx::Error> {
+    info!("Querying memberships of index {}", code);
+    query_as(
+        r#"
+        SELECT * FROM index_memberships
+        WHERE index_code = UPPER($1)
+        ORDER BY valid_to DESC NULLS FIRST, isin
+        "#,
+    )
+    .bind(code)
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::share_batches::tests::{insert_isins, ENEL, GENERALI};
+
+    fn composition(constituents: &[&str], complete: bool) -> IndexComposition {
+        IndexComposition {
+            level: IndexLevel {
+                code: "FTSEMIB".to_owned(),
+                name: "FTSE MIB".to_owned(),
+                valore: None,
+                var_percentuale: None,
+                var_assoluta: None,
+                updated_at: Utc::now(),
+            },
+            constituents: constituents.iter().map(|isin| isin.to_string()).collect(),
+            complete,
+        }
+    }
+
+    async fn open_members(pool: &PgPool) -> Vec<String> {
+        query_index_memberships("FTSEMIB", pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|membership| membership.valid_to.is_none())
+            .map(|membership| membership.isin)
+            .collect()
+    }
+
+    #[sqlx::test(migrator = "crate::migrations::MIGRATOR")]
+    async fn closes_memberships_of_removed_shares(pool: PgPool) {
+        insert_isins(&[ENEL, GENERALI], &pool).await;
+        insert_index(composition(&[ENEL, GENERALI], true), &pool)
+            .await
+            .unwrap();
+        assert_eq!(open_members(&pool).await, [GENERALI, ENEL]);
+
+        insert_index(composition(&[ENEL], true), &pool)
+            .await
+            .unwrap();
+        assert_eq!(open_members(&pool).await, [ENEL]);
+        assert_eq!(
+            query_index_memberships("FTSEMIB", &pool)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::migrations::MIGRATOR")]
+    async fn keeps_memberships_missing_from_incomplete_lists(pool: PgPool) {
+        insert_isins(&[ENEL, GENERALI], &pool).await;
+        insert_index(composition(&[GENERALI], true), &pool)
+            .await
+            .unwrap();
+
+        insert_index(composition(&[ENEL], false), &pool)
+            .await
+            .unwrap();
+        assert_eq!(open_members(&pool).await, [GENERALI, ENEL]);
+    }
+}
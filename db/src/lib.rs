@@ -1,32 +1,32 @@
+pub mod config;
+pub mod daily_bars;
+pub mod dividends;
+pub mod field_changes;
+pub mod indices;
 pub mod isins;
+pub mod maintenance;
 pub mod metrics;
+pub mod migrations;
+pub mod order_books;
+pub mod press_releases;
+pub mod price_snapshots;
+pub mod profiles;
+pub mod share_batches;
 pub mod shares;
+pub mod trades;
 pub mod utils;
 
-use dotenv::dotenv;
-use sqlx::{postgres::PgPoolOptions, Error, Pool, Postgres};
-use std::{env, time::Duration};
-use tracing::{error, info, warn};
+use sqlx::{Error, Pool, Postgres};
+use tracing::error;
 
-pub async fn connect() -> Result<Pool<Postgres>, Error> {
-    info!("Attempting to connecting to database");
-    match dotenv() {
-        Ok(_) => info!("Environment variables loaded successfully"),
-        Err(e) => warn!("Failed to load .env file: {}", e),
-    }
+pub use config::DbConfig;
 
-    let db_url = env::var("DATABASE_URL").map_err(|_| {
-        error!("DATABASE_URL environment variable is not set");
-        Error::from(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "DATABASE_URL environment variable is required",
-        ))
+// connects with the configuration of the environment, see `DbConfig::from_env`
+pub async fn connect() -> Result<Pool<Postgres>, Error> {
+    let config = DbConfig::from_env().map_err(|e| {
+        error!("Invalid database config: {}", e);
+        Error::Configuration(e.into())
     })?;
 
-    PgPoolOptions::new()
-        .max_connections(5)
-        .acquire_timeout(Duration::from_secs(10))
-        .connect(&db_url)
-        .await
-        .inspect_err(|e| error!("Database connection failed: {}", e))
+    config.connect().await
 }
@@ -0,0 +1,251 @@
+use serde::Serialize;
+use sqlx::{query_file, query_file_scalar, Pool, Postgres};
+use tracing::info;
+
+// tables partitioned by month, with their partition key
+const SNAPSHOT_TABLES: [(&str, &str); 2] = [
+    ("price_snapshots", "scraped_at"),
+    ("order_book_levels", "snapshot_at"),
+];
+
+#[derive(Debug, Clone)]
+pub struct RetentionPolicy {
+    // partitions created after the current month
+    pub months_ahead: i32,
+    // older snapshots are reduced to the last one of each day, the days done
+    // by previous runs are skipped
+    pub downsample_after_days: i32,
+    // partitions older than this are dropped, daily bars are kept
+    pub drop_after_months: i32,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            months_ahead: 2,
+            downsample_after_days: 30,
+            drop_after_months: 24,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct MaintenanceReport {
+    pub partitions_created: i64,
+    pub price_snapshots_removed: u64,
+    pub order_book_levels_removed: u64,
+    pub partitions_dropped: i64,
+}
+
+pub async fn maintain_snapshots(
+    policy: &RetentionPolicy,
+    pool: &Pool<Postgres>,
+) -> Result<MaintenanceReport, sqlx::Error> {
+    let mut report = MaintenanceReport::default();
+
+    for (table, key) in SNAPSHOT_TABLES {
+        report.partitions_created +=
+            create_partitions(table, key, policy.months_ahead, pool).await?;
+    }
+
+    info!(
+        "Downsampling snapshots older than {} days",
+        policy.downsample_after_days
+    );
+    // NOW() is the same for every statement of the transaction, so the recorded
+    // day is the cutoff the snapshots were downsampled to
+    let mut tx = pool.begin().await?;
+    report.price_snapshots_removed = query_file!(
+        "./queries/maintenance/downsample_price_snapshots.sql",
+        policy.downsample_after_days
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    report.order_book_levels_removed = query_file!(
+        "./queries/maintenance/downsample_order_books.sql",
+        policy.downsample_after_days
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    for (table, _) in SNAPSHOT_TABLES {
+        let until = query_file_scalar!(
+            "./queries/maintenance/record_downsampling.sql",
+            table,
+            policy.downsample_after_days
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        info!("Downsampled {} up to {}", table, until);
+    }
+    tx.commit().await?;
+
+    for (table, key) in SNAPSHOT_TABLES {
+        report.partitions_dropped +=
+            drop_partitions(table, key, policy.drop_after_months, pool).await?;
+    }
+
+    info!("Snapshot maintenance done, {:?}", report);
+    Ok(report)
+}
+
+pub async fn create_partitions(
+    table: &str,
+    key: &str,
+    months_ahead: i32,
+    pool: &Pool<Postgres>,
+) -> Result<i64, sqlx::Error> {
+    let created = query_file_scalar!(
+        "./queries/maintenance/create_partitions.sql",
+        table,
+        key,
+        months_ahead
+    )
+    .fetch_one(pool)
+    .await?;
+
+    info!("Created {} partitions of {}", created, table);
+    Ok(created)
+}
+
+pub async fn drop_partitions(
+    table: &str,
+    key: &str,
+    older_than_months: i32,
+    pool: &Pool<Postgres>,
+) -> Result<i64, sqlx::Error> {
+    let dropped = query_file_scalar!(
+        "./queries/maintenance/drop_partitions.sql",
+        table,
+        key,
+        older_than_months
+    )
+    .fetch_one(pool)
+    .await?;
+
+    info!("Dropped {} partitions of {}", dropped, table);
+    Ok(dropped as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::{query, query_scalar, PgPool};
+
+    use super::*;
+    use crate::share_batches::tests::{insert_isins, ENEL};
+
+    // at `hour` of the (Italian) day `days_ago`
+    const ROME_TIME: &str = "((NOW() AT TIME ZONE 'Europe/Rome')::DATE - $1::INT
+        + make_interval(hours => $2::INT))::TIMESTAMP AT TIME ZONE 'Europe/Rome'";
+
+    async fn insert_snapshots(days_ago: i32, hours: &[i32], pool: &PgPool) {
+        for hour in hours {
+            query(&format!(
+                "INSERT INTO price_snapshots (isin, scraped_at) VALUES ('{}', {})",
+                ENEL, ROME_TIME
+            ))
+            .bind(days_ago)
+            .bind(hour)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+    }
+
+    async fn insert_books(days_ago: i32, hours: &[i32], pool: &PgPool) {
+        for hour in hours {
+            query(&format!(
+                "INSERT INTO order_book_levels (isin, snapshot_at, side, level, price, quantity)
+                 SELECT '{}', {}, side, 1, 6.8, 100 FROM unnest(ARRAY['bid', 'ask']) side",
+                ENEL, ROME_TIME
+            ))
+            .bind(days_ago)
+            .bind(hour)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+    }
+
+    // snapshots left on the day `days_ago`
+    async fn snapshots_on(days_ago: i32, pool: &PgPool) -> i64 {
+        query_scalar(
+            "SELECT COUNT(*) FROM price_snapshots
+             WHERE (scraped_at AT TIME ZONE 'Europe/Rome')::DATE
+                 = (NOW() AT TIME ZONE 'Europe/Rome')::DATE - $1::INT",
+        )
+        .bind(days_ago)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn downsampled_days_ago(table: &str, pool: &PgPool) -> i32 {
+        query_scalar(
+            "SELECT (NOW() AT TIME ZONE 'Europe/Rome')::DATE - downsampled_until
+             FROM snapshot_downsampling WHERE table_name = $1",
+        )
+        .bind(table)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    fn policy(downsample_after_days: i32) -> RetentionPolicy {
+        RetentionPolicy {
+            downsample_after_days,
+            ..Default::default()
+        }
+    }
+
+    #[sqlx::test(migrator = "crate::migrations::MIGRATOR")]
+    async fn keeps_the_last_snapshot_of_old_days(pool: PgPool) {
+        insert_isins(&[ENEL], &pool).await;
+        insert_snapshots(40, &[9, 12, 17], &pool).await;
+        insert_snapshots(20, &[9, 17], &pool).await;
+        insert_books(40, &[9, 17], &pool).await;
+
+        let report = maintain_snapshots(&policy(30), &pool).await.unwrap();
+        assert_eq!(report.price_snapshots_removed, 2);
+        assert_eq!(report.order_book_levels_removed, 2);
+        assert_eq!(
+            (snapshots_on(40, &pool).await, snapshots_on(20, &pool).await),
+            (1, 2)
+        );
+
+        let last: i32 = query_scalar(
+            "SELECT EXTRACT(HOUR FROM scraped_at AT TIME ZONE 'Europe/Rome')::INT
+             FROM price_snapshots ORDER BY scraped_at LIMIT 1",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(last, 17);
+        for table in ["price_snapshots", "order_book_levels"] {
+            assert_eq!(downsampled_days_ago(table, &pool).await, 30, "{}", table);
+        }
+    }
+
+    #[sqlx::test(migrator = "crate::migrations::MIGRATOR")]
+    async fn skips_the_days_already_downsampled(pool: PgPool) {
+        insert_isins(&[ENEL], &pool).await;
+        insert_snapshots(20, &[9, 17], &pool).await;
+        maintain_snapshots(&policy(30), &pool).await.unwrap();
+
+        // rows added before the recorded day aren't scanned again
+        insert_snapshots(40, &[9, 17], &pool).await;
+        let report = maintain_snapshots(&policy(10), &pool).await.unwrap();
+        assert_eq!(report.price_snapshots_removed, 1);
+        assert_eq!(
+            (snapshots_on(40, &pool).await, snapshots_on(20, &pool).await),
+            (2, 1)
+        );
+        assert_eq!(downsampled_days_ago("price_snapshots", &pool).await, 10);
+
+        // a longer policy doesn't move the recorded day back
+        let report = maintain_snapshots(&policy(30), &pool).await.unwrap();
+        assert_eq!(report.price_snapshots_removed, 0);
+        assert_eq!(downsampled_days_ago("price_snapshots", &pool).await, 10);
+    }
+}
@@ -1,7 +1,10 @@
 use serde::Serialize;
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, Default)]
 pub struct InsertionMetrics {
     pub total: i32,
     pub successful: i32,
+    // only filled in where rows are inserted per ISIN
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failed_isins: Vec<String>,
 }
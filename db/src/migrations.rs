@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{
+    migrate::{MigrateError, Migrator},
+    query_as, query_scalar, Pool, Postgres,
+};
+use tracing::info;
+
+// the migrations are embedded at build time, see build.rs; databases created
+// before the migrations were consolidated still list the replaced versions as
+// applied, they are reported by migration_status instead of failing the run
+pub static MIGRATOR: Migrator = Migrator {
+    ignore_missing: true,
+    ..sqlx::migrate!("../migrations")
+};
+
+#[derive(Debug, Serialize)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    // None while pending
+    pub installed_on: Option<DateTime<Utc>>,
+    // the file was changed after being applied, migrating will fail
+    pub checksum_mismatch: bool,
+    // applied but no longer in migrations/
+    pub unknown: bool,
+}
+
+struct AppliedMigration {
+    description: String,
+    checksum: Vec<u8>,
+    installed_on: DateTime<Utc>,
+}
+
+// the migrations of migrations/ in order, then the unknown applied ones
+pub async fn migration_status(pool: &Pool<Postgres>) -> Result<Vec<MigrationStatus>, sqlx::Error> {
+    let mut applied = applied_migrations(pool).await?;
+
+    let mut status: Vec<MigrationStatus> = MIGRATOR
+        .iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .map(|migration| {
+            let installed = applied.remove(&migration.version);
+            MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                installed_on: installed.as_ref().map(|applied| applied.installed_on),
+                checksum_mismatch: installed
+                    .is_some_and(|applied| *applied.checksum != *migration.checksum),
+                unknown: false,
+            }
+        })
+        .collect();
+
+    status.extend(
+        applied
+            .into_iter()
+            .map(|(version, applied)| MigrationStatus {
+                version,
+                description: applied.description,
+                installed_on: Some(applied.installed_on),
+                checksum_mismatch: false,
+                unknown: true,
+            }),
+    );
+    Ok(status)
+}
+
+pub async fn pending_migrations(
+    pool: &Pool<Postgres>,
+) -> Result<Vec<MigrationStatus>, sqlx::Error> {
+    let mut status = migration_status(pool).await?;
+    status.retain(|migration| migration.installed_on.is_none());
+    Ok(status)
+}
+
+// returns the migrations that were applied
+pub async fn run_migrations(pool: &Pool<Postgres>) -> Result<Vec<MigrationStatus>, MigrateError> {
+    let pending = pending_migrations(pool).await?;
+    info!("Applying {} migrations", pending.len());
+
+    MIGRATOR.run(pool).await?;
+
+    Ok(pending)
+}
+
+// read without creating the migrations table, so that checking the status
+// doesn't change the database
+async fn applied_migrations(
+    pool: &Pool<Postgres>,
+) -> Result<BTreeMap<i64, AppliedMigration>, sqlx::Error> {
+    let exists: bool = query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Ok(BTreeMap::new());
+    }
+
+    let applied: Vec<(i64, String, Vec<u8>, DateTime<Utc>)> = query_as(
+        "SELECT version, description, checksum, installed_on FROM _sqlx_migrations WHERE success",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(applied
+        .into_iter()
+        .map(|(version, description, checksum, installed_on)| {
+            let applied = AppliedMigration {
+                description,
+                checksum,
+                installed_on,
+            };
+            (version, applied)
+        })
+        .collect())
+}
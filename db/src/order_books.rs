@@ -0,0 +1,177 @@
+use scraper::shares::{BookSide, OrderBook, OrderBookLevel};
+use sqlx::{query_as, query_file, Pool, Postgres, Transaction};
+use tracing::{info, warn};
+
+#[derive(sqlx::FromRow)]
+struct OrderBookLevelRow {
+    isin: String,
+    snapshot_at: chrono::DateTime<chrono::Utc>,
+    side: String,
+    level: i16,
+    price: rust_decimal::Decimal,
+    quantity: i64,
+    orders: Option<i32>,
+}
+
+pub async fn insert_order_book(
+    order_book: &OrderBook,
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<(), sqlx::Error> {
+    let levels = order_book
+        .bids
+        .iter()
+        .map(|level| (BookSide::Bid, level))
+        .chain(order_book.asks.iter().map(|level| (BookSide::Ask, level)));
+
+    let mut sides = Vec::new();
+    let mut level_nums = Vec::new();
+    let mut prices = Vec::new();
+    let mut quantities = Vec::new();
+    let mut orders = Vec::new();
+    for (side, level) in levels {
+        sides.push(side.as_str().to_owned());
+        level_nums.push(level.level as i16);
+        prices.push(level.price);
+        quantities.push(level.quantity as i64);
+        orders.push(level.orders.map(|o| o as i32));
+    }
+
+    query_file!(
+        "./queries/share/insert_order_book.sql",
+        order_book.isin,
+        order_book.updated_at,
+        &sides,
+        &level_nums,
+        &prices,
+        &quantities,
+        &orders as &[Option<i32>]
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn query_latest_order_book(
+    isin: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<OrderBook>, sqlx::Error> {
+    info!("Querying latest order book for {}", isin);
+    let rows: Vec<OrderBookLevelRow> = query_as(
+        r#"
+        SELECT isin, snapshot_at, side, level, price, quantity, orders
+        FROM order_book_levels
+        WHERE isin = $1
+          AND snapshot_at = (SELECT MAX(snapshot_at) FROM order_book_levels WHERE isin = $1)
+        ORDER BY side, level
+        "#,
+    )
+    .bind(isin)
+    .fetch_all(pool)
+    .await?;
+
+    let Some(first) = rows.first() else {
+        warn!("Found no order book for {}", isin);
+        return Ok(None);
+    };
+
+    let mut order_book = OrderBook {
+        isin: first.isin.clone(),
+        bids: Vec::new(),
+        asks: Vec::new(),
+        updated_at: first.snapshot_at,
+    };
+    for row in rows {
+        let level = OrderBookLevel {
+            level: row.level as u16,
+            price: row.price,
+            quantity: row.quantity as u64,
+            orders: row.orders.map(|o| o as u64),
+        };
+        match row.side.as_str() {
+            "bid" => order_book.bids.push(level),
+            _ => order_book.asks.push(level),
+        }
+    }
+
+    Ok(Some(order_book))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, Utc};
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::share_batches::tests::{dec, insert_isins, level, now, ENEL};
+
+    fn book(
+        bids: Vec<OrderBookLevel>,
+        asks: Vec<OrderBookLevel>,
+        updated_at: DateTime<Utc>,
+    ) -> OrderBook {
+        OrderBook {
+            isin: ENEL.to_owned(),
+            bids,
+            asks,
+            updated_at,
+        }
+    }
+
+    async fn store(order_book: &OrderBook, pool: &PgPool) {
+        let mut tx = pool.begin().await.unwrap();
+        insert_order_book(order_book, &mut tx).await.unwrap();
+        tx.commit().await.unwrap();
+    }
+
+    #[sqlx::test(migrator = "crate::migrations::MIGRATOR")]
+    async fn queries_the_latest_book(pool: PgPool) {
+        insert_isins(&[ENEL], &pool).await;
+        assert!(query_latest_order_book(ENEL, &pool)
+            .await
+            .unwrap()
+            .is_none());
+
+        let scraped_at = now();
+        let full = book(
+            vec![
+                level(1, "6.866", 1200, Some(3)),
+                level(2, "6.864", 500, None),
+            ],
+            vec![level(1, "6.868", 900, Some(2))],
+            scraped_at - Duration::minutes(5),
+        );
+        store(&full, &pool).await;
+
+        let stored = query_latest_order_book(ENEL, &pool).await.unwrap().unwrap();
+        assert_eq!(stored.bids, full.bids);
+        assert_eq!(stored.asks, full.asks);
+        assert_eq!(stored.updated_at, full.updated_at);
+        assert_eq!(stored.spread(), Some(dec("0.002")));
+
+        // the levels of older snapshots aren't mixed in
+        let bids_only = book(vec![level(1, "6.87", 300, Some(1))], Vec::new(), scraped_at);
+        store(&bids_only, &pool).await;
+
+        let stored = query_latest_order_book(ENEL, &pool).await.unwrap().unwrap();
+        assert_eq!(stored.bids, bids_only.bids);
+        assert!(stored.asks.is_empty());
+        assert_eq!(stored.spread(), None);
+    }
+
+    #[sqlx::test(migrator = "crate::migrations::MIGRATOR")]
+    async fn has_no_spread_without_bids(pool: PgPool) {
+        insert_isins(&[ENEL], &pool).await;
+        let asks_only = book(
+            Vec::new(),
+            vec![level(1, "6.868", 900, Some(2)), level(2, "6.87", 500, None)],
+            now(),
+        );
+        store(&asks_only, &pool).await;
+
+        let stored = query_latest_order_book(ENEL, &pool).await.unwrap().unwrap();
+        assert!(stored.bids.is_empty());
+        assert_eq!(stored.asks, asks_only.asks);
+        assert_eq!(stored.spread(), None);
+    }
+}
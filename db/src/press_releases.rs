@@ -0,0 +1,234 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use scraper::press_releases::types::PressRelease;
+use serde::Deserialize;
+use sqlx::{query_as, query_file, Pool, Postgres, QueryBuilder};
+use tracing::{error, info, warn};
+
+use crate::metrics::InsertionMetrics;
+use crate::utils::{empty_string_as_none, push_condition};
+
+const PRESS_RELEASES_BATCH_SIZE: usize = 200;
+const MAX_SEARCH_RESULTS: i64 = 100;
+
+const INITIAL_PRESS_RELEASE_QUERY: &str = r#"
+    SELECT isin, link, title, published_at, body, updated_at
+    FROM press_releases
+"#;
+
+#[derive(Deserialize, Debug, Default)]
+pub struct PressReleaseQuery {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub isin: Option<String>,
+    // inclusive, as YYYY-MM-DD
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub from: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub to: Option<NaiveDate>,
+    // web search syntax, e.g. "dividendo -acconto"
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub text: Option<String>,
+}
+
+pub async fn insert_all_press_releases(
+    releases: Vec<PressRelease>,
+    pool: &Pool<Postgres>,
+) -> InsertionMetrics {
+    let release_num = releases.len() as i32;
+    let mut successful_inserts = 0;
+
+    info!("Inserting a total of {} press releases", release_num);
+
+    for batch in releases.chunks(PRESS_RELEASES_BATCH_SIZE) {
+        match insert_press_releases(batch, pool).await {
+            Ok(_) => successful_inserts += batch.len() as i32,
+            Err(e) => error!("Unable to insert {} press releases, {}", batch.len(), e),
+        }
+    }
+
+    InsertionMetrics {
+        total: release_num,
+        successful: successful_inserts,
+        ..Default::default()
+    }
+}
+
+pub async fn insert_press_releases(
+    releases: &[PressRelease],
+    pool: &Pool<Postgres>,
+) -> Result<u64, sqlx::Error> {
+    let isins: Vec<String> = releases.iter().map(|r| r.isin.clone()).collect();
+    let links: Vec<String> = releases.iter().map(|r| r.link.clone()).collect();
+    let titles: Vec<String> = releases.iter().map(|r| r.title.clone()).collect();
+    let published_ats: Vec<DateTime<Utc>> = releases.iter().map(|r| r.published_at).collect();
+    let bodies: Vec<Option<String>> = releases.iter().map(|r| r.body.clone()).collect();
+    let updated_ats: Vec<DateTime<Utc>> = releases.iter().map(|r| r.updated_at).collect();
+
+    let res = query_file!(
+        "./queries/press_release/insert_press_releases.sql",
+        &isins,
+        &links,
+        &titles,
+        &published_ats,
+        &bodies as &[Option<String>],
+        &updated_ats
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(res.rows_affected())
+}
+
+// the links of each isin whose body is stored, the others are fetched again
+pub async fn query_stored_release_links(
+    pool: &Pool<Postgres>,
+) -> Result<HashMap<String, HashSet<String>>, sqlx::Error> {
+    let rows: Vec<(String, String)> =
+        query_as("SELECT isin, link FROM press_releases WHERE body IS NOT NULL")
+            .fetch_all(pool)
+            .await?;
+
+    let mut links: HashMap<String, HashSet<String>> = HashMap::new();
+    for (isin, link) in rows {
+        links.entry(isin).or_default().insert(link);
+    }
+    info!(
+        "Found stored bodies of {} press releases",
+        links.values().map(HashSet::len).sum::<usize>()
+    );
+
+    Ok(links)
+}
+
+// with a text the results are ranked by relevance, otherwise newest first
+pub async fn search_press_releases(
+    query: PressReleaseQuery,
+    pool: &Pool<Postgres>,
+) -> Result<Vec<PressRelease>, sqlx::Error> {
+    info!("Searching press releases with {:?}", query);
+    let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(INITIAL_PRESS_RELEASE_QUERY);
+    let mut has_conditions = false;
+
+    if let Some(isin) = query.isin {
+        push_condition(&mut query_builder, &mut has_conditions);
+        query_builder.push("isin = ").push_bind(isin);
+    }
+    // dates are days of the exchange, compared as the instants they start at so
+    // that published_at can use its index
+    if let Some(from) = query.from {
+        push_condition(&mut query_builder, &mut has_conditions);
+        query_builder
+            .push("published_at >= ")
+            .push_bind(from)
+            .push("::DATE::TIMESTAMP AT TIME ZONE 'Europe/Rome'");
+    }
+    if let Some(to) = query.to {
+        push_condition(&mut query_builder, &mut has_conditions);
+        query_builder
+            .push("published_at < (")
+            .push_bind(to)
+            .push("::DATE + 1)::TIMESTAMP AT TIME ZONE 'Europe/Rome'");
+    }
+
+    if let Some(text) = query.text {
+        push_condition(&mut query_builder, &mut has_conditions);
+        query_builder
+            .push("search_vector @@ websearch_to_tsquery('italian', ")
+            .push_bind(text.clone())
+            .push(")")
+            .push(" ORDER BY ts_rank(search_vector, websearch_to_tsquery('italian', ")
+            .push_bind(text)
+            .push(")) DESC, published_at DESC");
+    } else {
+        query_builder.push(" ORDER BY published_at DESC");
+    }
+    query_builder.push(" LIMIT ").push_bind(MAX_SEARCH_RESULTS);
+
+    let res = query_builder.build_query_as().fetch_all(pool).await?;
+
+    if res.is_empty() {
+        warn!("Found no press release");
+    } else {
+        info!("Got a total of {} press releases", res.len());
+    }
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::share_batches::tests::{insert_isins, ENEL, GENERALI};
+
+    fn release(isin: &str, link: &str, published_at: &str, body: Option<&str>) -> PressRelease {
+        PressRelease {
+            isin: isin.to_owned(),
+            link: link.to_owned(),
+            title: format!("Comunicato {}", link),
+            published_at: published_at.parse().unwrap(),
+            body: body.map(str::to_owned),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[sqlx::test(migrator = "crate::migrations::MIGRATOR")]
+    async fn queries_the_links_with_a_body(pool: PgPool) {
+        insert_isins(&[ENEL, GENERALI], &pool).await;
+        insert_press_releases(
+            &[
+                release(ENEL, "/a", "2024-11-29T16:35:00Z", Some("Risultati")),
+                release(ENEL, "/b", "2024-11-29T16:40:00Z", None),
+                release(GENERALI, "/c", "2024-11-29T16:45:00Z", Some("Dividendo")),
+            ],
+            &pool,
+        )
+        .await
+        .unwrap();
+
+        // a refresh without the body keeps it
+        insert_press_releases(&[release(ENEL, "/a", "2024-11-29T16:35:00Z", None)], &pool)
+            .await
+            .unwrap();
+
+        let links = query_stored_release_links(&pool).await.unwrap();
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[ENEL], HashSet::from(["/a".to_owned()]));
+        assert_eq!(links[GENERALI], HashSet::from(["/c".to_owned()]));
+    }
+
+    #[sqlx::test(migrator = "crate::migrations::MIGRATOR")]
+    async fn filters_by_days_of_the_exchange(pool: PgPool) {
+        insert_isins(&[ENEL], &pool).await;
+        insert_press_releases(
+            &[
+                // 29/11 at 00:30 and 23:30 in Rome
+                release(ENEL, "/first", "2024-11-28T23:30:00Z", None),
+                release(ENEL, "/last", "2024-11-29T22:30:00Z", None),
+                // 30/11 at 00:30 in Rome
+                release(ENEL, "/next", "2024-11-29T23:30:00Z", None),
+                // 28/11 at 23:30 in Rome
+                release(ENEL, "/previous", "2024-11-28T22:30:00Z", None),
+            ],
+            &pool,
+        )
+        .await
+        .unwrap();
+
+        let day = NaiveDate::from_ymd_opt(2024, 11, 29);
+        let query = PressReleaseQuery {
+            from: day,
+            to: day,
+            ..Default::default()
+        };
+        let links: Vec<String> = search_press_releases(query, &pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|release| release.link)
+            .collect();
+        assert_eq!(links, ["/last", "/first"]);
+    }
+}
@@ -0,0 +1,102 @@
+use chrono::{DateTime, Utc};
+use scraper::shares::PriceData;
+use serde::Deserialize;
+use sqlx::{query_as, query_file, Pool, Postgres, Transaction};
+use tracing::info;
+
+#[derive(Deserialize, Debug)]
+pub struct PriceHistoryQuery {
+    pub isin: String,
+    // scrape times as RFC 3339, `to` is exclusive
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+pub async fn insert_price_snapshot(
+    price_data: &PriceData,
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<(), sqlx::Error> {
+    query_file!(
+        "./queries/share/insert_price_snapshot.sql",
+        price_data.isin,
+        price_data
+            .fase_di_mercato
+            .as_ref()
+            .map(|phase| phase.to_string()),
+        price_data.prezzo_ultimo_contratto,
+        price_data.var_percentuale,
+        price_data.var_assoluta,
+        price_data.pr_medio_progr,
+        price_data.data_ora_ultimo_contratto,
+        price_data.quantita_ultimo,
+        price_data.quantita_totale,
+        price_data.numero_contratti.map(|i| i as i32),
+        price_data.controvalore,
+        price_data.max_oggi,
+        price_data.max_anno.as_ref().and_then(|e| e.price),
+        price_data.max_anno.as_ref().and_then(|e| e.date),
+        price_data.min_oggi,
+        price_data.min_anno.as_ref().and_then(|e| e.price),
+        price_data.min_anno.as_ref().and_then(|e| e.date),
+        price_data.chiusura_precedente,
+        price_data.prezzo_riferimento.as_ref().and_then(|e| e.price),
+        price_data
+            .prezzo_riferimento
+            .as_ref()
+            .and_then(|e| e.datetime),
+        price_data.prezzo_ufficiale.as_ref().and_then(|e| e.price),
+        price_data.prezzo_ufficiale.as_ref().and_then(|e| e.date),
+        price_data.apertura_odierna,
+        price_data.updated_at
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+// snapshots scraped in [from, to), oldest first; `updated_at` is the scrape time
+pub async fn query_price_snapshots(
+    query: &PriceHistoryQuery,
+    pool: &Pool<Postgres>,
+) -> Result<Vec<PriceData>, sqlx::Error> {
+    info!(
+        "Querying price snapshots for {} from {} to {}",
+        query.isin, query.from, query.to
+    );
+    query_as(
+        r#"
+        SELECT *, scraped_at AS updated_at
+        FROM price_snapshots
+        WHERE isin = $1 AND scraped_at >= $2 AND scraped_at < $3
+        ORDER BY scraped_at
+        "#,
+    )
+    .bind(&query.isin)
+    .bind(query.from)
+    .bind(query.to)
+    .fetch_all(pool)
+    .await
+}
+
+// the snapshot current at `at`, i.e. the last one scraped before it
+pub async fn query_price_snapshot_at(
+    isin: &str,
+    at: DateTime<Utc>,
+    pool: &Pool<Postgres>,
+) -> Result<Option<PriceData>, sqlx::Error> {
+    info!("Querying price snapshot for {} at {}", isin, at);
+    query_as(
+        r#"
+        SELECT *, scraped_at AS updated_at
+        FROM price_snapshots
+        WHERE isin = $1 AND scraped_at <= $2
+        ORDER BY scraped_at DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(isin)
+    .bind(at)
+    .fetch_optional(pool)
+    .await
+}
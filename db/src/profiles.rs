@@ -0,0 +1,92 @@
+use chrono::TimeDelta;
+use futures::{stream::FuturesUnordered, StreamExt};
+use scraper::{isins::types::ShareIsin, shares::CompanyProfile};
+use sqlx::{postgres::types::PgInterval, query_as, query_file, Pool, Postgres};
+use tracing::{error, info};
+
+use crate::metrics::InsertionMetrics;
+
+// shares without a profile are always included
+const SHARE_ISINS_WITH_OLD_PROFILE: &str = r#"
+    SELECT
+        si.isin,
+        si.share_name,
+        COALESCE(cp.updated_at, '1970-01-01'::TIMESTAMPTZ) AS updated_at
+    FROM share_isins si
+    LEFT JOIN company_profiles cp ON si.isin = cp.isin
+    WHERE cp.updated_at IS NULL OR cp.updated_at <= NOW() - $1::INTERVAL
+"#;
+
+pub async fn get_profiles_to_refresh(
+    pool: &Pool<Postgres>,
+    min_duration: TimeDelta,
+) -> Result<Vec<ShareIsin>, sqlx::Error> {
+    query_as(SHARE_ISINS_WITH_OLD_PROFILE)
+        .bind(PgInterval {
+            months: 0,
+            days: 0,
+            microseconds: min_duration.num_microseconds().unwrap_or_default(),
+        })
+        .fetch_all(pool)
+        .await
+}
+
+pub async fn insert_all_profiles(
+    profiles: Vec<CompanyProfile>,
+    pool: &Pool<Postgres>,
+) -> InsertionMetrics {
+    let profile_num = profiles.len() as i32;
+    let mut tasks = FuturesUnordered::new();
+
+    info!("Inserting a total of {} company profiles", profile_num);
+
+    for profile in profiles {
+        tasks.push(insert_profile(profile, pool));
+    }
+
+    let mut successful_inserts = 0;
+    while let Some(res) = tasks.next().await {
+        if let Err(e) = res {
+            error!("Unable to insert company profile, {}", e);
+        } else {
+            successful_inserts += 1;
+        }
+    }
+
+    InsertionMetrics {
+        total: profile_num,
+        successful: successful_inserts,
+        ..Default::default()
+    }
+}
+
+pub async fn insert_profile(
+    profile: CompanyProfile,
+    pool: &Pool<Postgres>,
+) -> Result<sqlx::postgres::PgQueryResult, sqlx::Error> {
+    query_file!(
+        "./queries/profile/insert_company_profile.sql",
+        profile.isin,
+        profile.ragione_sociale,
+        profile.indirizzo,
+        profile.sito_web,
+        profile.descrizione,
+        profile.azioni_in_circolazione.map(|v| v as i64),
+        profile.flottante,
+        profile.data_quotazione,
+        profile.updated_at
+    )
+    .execute(pool)
+    .await
+}
+
+pub async fn query_company_profile(
+    isin: &str,
+    pool: &Pool<Postgres>,
+) -> Result<Option<CompanyProfile>, sqlx::Error> {
+    info!("Querying company profile for {}", isin);
+    query_as("SELECT * FROM company_profiles WHERE isin = $1")
+        .bind(isin)
+        .fetch_optional(pool)
+        .await
+}
@@ -0,0 +1,578 @@
+// upserts a batch of shares with one statement per table, the arrays of each
+// statement are unnested server side
+//
+// the ISINs of a batch must be unique, an upsert can't touch a row twice
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use scraper::shares::{BookSide, PriceData, Share};
+use sqlx::{query_file, Pool, Postgres, Transaction};
+
+#[derive(Default)]
+struct PriceDataColumns {
+    isins: Vec<String>,
+    fasi_di_mercato: Vec<Option<String>>,
+    prezzi_ultimo_contratto: Vec<Option<Decimal>>,
+    var_percentuali: Vec<Option<Decimal>>,
+    var_assolute: Vec<Option<Decimal>>,
+    pr_medi_progr: Vec<Option<Decimal>>,
+    date_ora_ultimo_contratto: Vec<Option<DateTime<Utc>>>,
+    quantita_ultimo: Vec<Option<Decimal>>,
+    quantita_totale: Vec<Option<Decimal>>,
+    numeri_contratti: Vec<Option<i32>>,
+    controvalori: Vec<Option<Decimal>>,
+    max_oggi: Vec<Option<Decimal>>,
+    max_anno: Vec<Option<Decimal>>,
+    max_anno_dates: Vec<Option<NaiveDate>>,
+    min_oggi: Vec<Option<Decimal>>,
+    min_anno: Vec<Option<Decimal>>,
+    min_anno_dates: Vec<Option<NaiveDate>>,
+    chiusure_precedenti: Vec<Option<Decimal>>,
+    prezzi_riferimento: Vec<Option<Decimal>>,
+    date_ora_prezzo_riferimento: Vec<Option<DateTime<Utc>>>,
+    prezzi_ufficiali: Vec<Option<Decimal>>,
+    date_prezzo_ufficiale: Vec<Option<NaiveDate>>,
+    aperture_odierne: Vec<Option<Decimal>>,
+    updated_ats: Vec<DateTime<Utc>>,
+}
+
+impl PriceDataColumns {
+    fn push(&mut self, price_data: &PriceData) {
+        self.isins.push(price_data.isin.clone());
+        self.fasi_di_mercato.push(
+            price_data
+                .fase_di_mercato
+                .as_ref()
+                .map(|phase| phase.to_string()),
+        );
+        self.prezzi_ultimo_contratto
+            .push(price_data.prezzo_ultimo_contratto);
+        self.var_percentuali.push(price_data.var_percentuale);
+        self.var_assolute.push(price_data.var_assoluta);
+        self.pr_medi_progr.push(price_data.pr_medio_progr);
+        self.date_ora_ultimo_contratto
+            .push(price_data.data_ora_ultimo_contratto);
+        self.quantita_ultimo.push(price_data.quantita_ultimo);
+        self.quantita_totale.push(price_data.quantita_totale);
+        self.numeri_contratti
+            .push(price_data.numero_contratti.map(|i| i as i32));
+        self.controvalori.push(price_data.controvalore);
+        self.max_oggi.push(price_data.max_oggi);
+        self.max_anno
+            .push(price_data.max_anno.as_ref().and_then(|e| e.price));
+        self.max_anno_dates
+            .push(price_data.max_anno.as_ref().and_then(|e| e.date));
+        self.min_oggi.push(price_data.min_oggi);
+        self.min_anno
+            .push(price_data.min_anno.as_ref().and_then(|e| e.price));
+        self.min_anno_dates
+            .push(price_data.min_anno.as_ref().and_then(|e| e.date));
+        self.chiusure_precedenti
+            .push(price_data.chiusura_precedente);
+        self.prezzi_riferimento
+            .push(price_data.prezzo_riferimento.as_ref().and_then(|e| e.price));
+        self.date_ora_prezzo_riferimento.push(
+            price_data
+                .prezzo_riferimento
+                .as_ref()
+                .and_then(|e| e.datetime),
+        );
+        self.prezzi_ufficiali
+            .push(price_data.prezzo_ufficiale.as_ref().and_then(|e| e.price));
+        self.date_prezzo_ufficiale
+            .push(price_data.prezzo_ufficiale.as_ref().and_then(|e| e.date));
+        self.aperture_odierne.push(price_data.apertura_odierna);
+        self.updated_ats.push(price_data.updated_at);
+    }
+}
+
+pub(crate) async fn insert_share_batch(
+    shares: &[Share],
+    pool: &Pool<Postgres>,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    insert_details(shares, &mut tx).await?;
+    insert_market_information(shares, &mut tx).await?;
+    insert_price_data(shares, &mut tx).await?;
+    insert_order_books(shares, &mut tx).await?;
+    insert_performance_metrics(shares, &mut tx).await?;
+
+    tx.commit().await
+}
+
+async fn insert_details(
+    shares: &[Share],
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<(), sqlx::Error> {
+    let details = shares.iter().map(|share| &share.share_details);
+
+    query_file!(
+        "./queries/share/bulk_insert_details.sql",
+        &details.clone().map(|d| d.isin.clone()).collect::<Vec<_>>(),
+        &details.clone().map(|d| d.id_strumento).collect::<Vec<_>>() as &[Option<f64>],
+        &details
+            .clone()
+            .map(|d| d.codice_alfanumerico.clone())
+            .collect::<Vec<_>>() as &[Option<String>],
+        &details.map(|d| d.updated_at).collect::<Vec<_>>()
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+async fn insert_market_information(
+    shares: &[Share],
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<(), sqlx::Error> {
+    let infos = shares.iter().map(|share| &share.market_information);
+    let segments: Vec<Option<String>> = infos
+        .clone()
+        .map(|i| i.mercato_segmento.as_ref().map(|s| s.to_string()))
+        .collect();
+
+    let new_segments: Vec<String> = segments.iter().flatten().cloned().collect();
+    query_file!(
+        "./queries/share/bulk_insert_market_segments.sql",
+        &new_segments
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    query_file!(
+        "./queries/share/bulk_insert_market_info.sql",
+        &infos.clone().map(|i| i.isin.clone()).collect::<Vec<_>>(),
+        &infos
+            .clone()
+            .map(|i| i.super_sector.clone())
+            .collect::<Vec<_>>() as &[Option<String>],
+        &segments as &[Option<String>],
+        &infos
+            .clone()
+            .map(|i| i.capitalizzazione_di_mercato)
+            .collect::<Vec<_>>() as &[Option<Decimal>],
+        &infos.clone().map(|i| i.lotto_minimo).collect::<Vec<_>>() as &[Option<f64>],
+        &infos.map(|i| i.updated_at).collect::<Vec<_>>()
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+// the snapshots take the same columns as price_data, with the scrape time as scraped_at
+async fn insert_price_data(
+    shares: &[Share],
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<(), sqlx::Error> {
+    let mut c = PriceDataColumns::default();
+    for share in shares {
+        c.push(&share.price_data);
+    }
+
+    let new_phases: Vec<String> = c.fasi_di_mercato.iter().flatten().cloned().collect();
+    query_file!("./queries/share/bulk_insert_market_phases.sql", &new_phases)
+        .execute(&mut **tx)
+        .await?;
+
+    query_file!(
+        "./queries/share/bulk_insert_price_snapshots.sql",
+        &c.isins,
+        &c.fasi_di_mercato as &[Option<String>],
+        &c.prezzi_ultimo_contratto as &[Option<Decimal>],
+        &c.var_percentuali as &[Option<Decimal>],
+        &c.var_assolute as &[Option<Decimal>],
+        &c.pr_medi_progr as &[Option<Decimal>],
+        &c.date_ora_ultimo_contratto as &[Option<DateTime<Utc>>],
+        &c.quantita_ultimo as &[Option<Decimal>],
+        &c.quantita_totale as &[Option<Decimal>],
+        &c.numeri_contratti as &[Option<i32>],
+        &c.controvalori as &[Option<Decimal>],
+        &c.max_oggi as &[Option<Decimal>],
+        &c.max_anno as &[Option<Decimal>],
+        &c.max_anno_dates as &[Option<NaiveDate>],
+        &c.min_oggi as &[Option<Decimal>],
+        &c.min_anno as &[Option<Decimal>],
+        &c.min_anno_dates as &[Option<NaiveDate>],
+        &c.chiusure_precedenti as &[Option<Decimal>],
+        &c.prezzi_riferimento as &[Option<Decimal>],
+        &c.date_ora_prezzo_riferimento as &[Option<DateTime<Utc>>],
+        &c.prezzi_ufficiali as &[Option<Decimal>],
+        &c.date_prezzo_ufficiale as &[Option<NaiveDate>],
+        &c.aperture_odierne as &[Option<Decimal>],
+        &c.updated_ats
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    query_file!(
+        "./queries/share/bulk_insert_price_data.sql",
+        &c.isins,
+        &c.fasi_di_mercato as &[Option<String>],
+        &c.prezzi_ultimo_contratto as &[Option<Decimal>],
+        &c.var_percentuali as &[Option<Decimal>],
+        &c.var_assolute as &[Option<Decimal>],
+        &c.pr_medi_progr as &[Option<Decimal>],
+        &c.date_ora_ultimo_contratto as &[Option<DateTime<Utc>>],
+        &c.quantita_ultimo as &[Option<Decimal>],
+        &c.quantita_totale as &[Option<Decimal>],
+        &c.numeri_contratti as &[Option<i32>],
+        &c.controvalori as &[Option<Decimal>],
+        &c.max_oggi as &[Option<Decimal>],
+        &c.max_anno as &[Option<Decimal>],
+        &c.max_anno_dates as &[Option<NaiveDate>],
+        &c.min_oggi as &[Option<Decimal>],
+        &c.min_anno as &[Option<Decimal>],
+        &c.min_anno_dates as &[Option<NaiveDate>],
+        &c.chiusure_precedenti as &[Option<Decimal>],
+        &c.prezzi_riferimento as &[Option<Decimal>],
+        &c.date_ora_prezzo_riferimento as &[Option<DateTime<Utc>>],
+        &c.prezzi_ufficiali as &[Option<Decimal>],
+        &c.date_prezzo_ufficiale as &[Option<NaiveDate>],
+        &c.aperture_odierne as &[Option<Decimal>],
+        &c.updated_ats
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+async fn insert_order_books(
+    shares: &[Share],
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<(), sqlx::Error> {
+    let mut isins = Vec::new();
+    let mut snapshot_ats = Vec::new();
+    let mut sides = Vec::new();
+    let mut level_nums = Vec::new();
+    let mut prices = Vec::new();
+    let mut quantities = Vec::new();
+    let mut orders = Vec::new();
+
+    let order_books = shares.iter().filter_map(|share| share.order_book.as_ref());
+    for order_book in order_books {
+        let levels = order_book
+            .bids
+            .iter()
+            .map(|level| (BookSide::Bid, level))
+            .chain(order_book.asks.iter().map(|level| (BookSide::Ask, level)));
+        for (side, level) in levels {
+            isins.push(order_book.isin.clone());
+            snapshot_ats.push(order_book.updated_at);
+            sides.push(side.as_str().to_owned());
+            level_nums.push(level.level as i16);
+            prices.push(level.price);
+            quantities.push(level.quantity as i64);
+            orders.push(level.orders.map(|o| o as i32));
+        }
+    }
+    if isins.is_empty() {
+        return Ok(());
+    }
+
+    query_file!(
+        "./queries/share/bulk_insert_order_books.sql",
+        &isins,
+        &snapshot_ats,
+        &sides,
+        &level_nums,
+        &prices,
+        &quantities,
+        &orders as &[Option<i32>]
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+async fn insert_performance_metrics(
+    shares: &[Share],
+    tx: &mut Transaction<'_, Postgres>,
+) -> Result<(), sqlx::Error> {
+    let metrics = shares.iter().map(|share| &share.performance_metrics);
+
+    query_file!(
+        "./queries/share/bulk_insert_performance_metrics.sql",
+        &metrics.clone().map(|m| m.isin.clone()).collect::<Vec<_>>(),
+        &metrics
+            .clone()
+            .map(|m| m.performance_1_mese)
+            .collect::<Vec<_>>() as &[Option<Decimal>],
+        &metrics
+            .clone()
+            .map(|m| m.performance_6_mesi)
+            .collect::<Vec<_>>() as &[Option<Decimal>],
+        &metrics
+            .clone()
+            .map(|m| m.performance_1_anno)
+            .collect::<Vec<_>>() as &[Option<Decimal>],
+        &metrics.map(|m| m.updated_at).collect::<Vec<_>>()
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::str::FromStr;
+
+    use chrono::SubsecRound;
+    use scraper::{
+        isins::types::ShareIsin,
+        shares::{MarketPhase, MarketSegment, OrderBook, OrderBookLevel, ScrapableStruct},
+    };
+    use sqlx::{query, query_scalar, PgPool};
+
+    use super::*;
+    use crate::{
+        order_books::query_latest_order_book,
+        shares::{query_share_with, ShareQuery},
+    };
+
+    pub(crate) const ENEL: &str = "IT0003128367";
+    pub(crate) const GENERALI: &str = "IT0000062072";
+    pub(crate) const MEDIOBANCA: &str = "IT0000062957";
+
+    pub(crate) fn dec(text: &str) -> Decimal {
+        Decimal::from_str(text).unwrap()
+    }
+
+    // the database keeps microseconds
+    pub(crate) fn now() -> DateTime<Utc> {
+        Utc::now().trunc_subsecs(6)
+    }
+
+    pub(crate) fn level(
+        level: u16,
+        price: &str,
+        quantity: u64,
+        orders: Option<u64>,
+    ) -> OrderBookLevel {
+        OrderBookLevel {
+            level,
+            price: dec(price),
+            quantity,
+            orders,
+        }
+    }
+
+    // a share with a value in every column
+    pub(crate) fn test_share(isin: &str, price: &str, scraped_at: DateTime<Utc>) -> Share {
+        let share_isin = ShareIsin::new(isin.to_owned(), isin.to_owned()).unwrap();
+        let mut share = Share::with_isin(&share_isin);
+        share.updated_at = scraped_at;
+
+        let details = &mut share.share_details;
+        details.id_strumento = Some(4281.0);
+        details.codice_alfanumerico = Some("ENEL".to_owned());
+        details.updated_at = scraped_at;
+
+        let info = &mut share.market_information;
+        info.super_sector = Some("Utilities".to_owned());
+        info.mercato_segmento = Some(MarketSegment::BlueChip);
+        info.capitalizzazione_di_mercato = Some(dec("69821740000"));
+        info.lotto_minimo = Some(1.0);
+        info.updated_at = scraped_at;
+
+        let price_data = &mut share.price_data;
+        price_data.fase_di_mercato = Some(MarketPhase::ContinuousTrading);
+        price_data.prezzo_ultimo_contratto = Some(dec(price));
+        price_data.var_percentuale = Some(dec("-0.45"));
+        price_data.data_ora_ultimo_contratto = Some(scraped_at);
+        price_data.quantita_totale = Some(dec("12345678"));
+        price_data.numero_contratti = Some(4321);
+        let max_anno = price_data.max_anno.insert(Default::default());
+        max_anno.price = Some(dec("7.254"));
+        max_anno.date = NaiveDate::from_ymd_opt(2024, 5, 20);
+        let prezzo_riferimento = price_data.prezzo_riferimento.insert(Default::default());
+        prezzo_riferimento.price = Some(dec("6.87"));
+        prezzo_riferimento.datetime = Some(scraped_at);
+        price_data.updated_at = scraped_at;
+
+        let metrics = &mut share.performance_metrics;
+        metrics.performance_1_mese = Some(dec("1.5"));
+        metrics.performance_6_mesi = Some(dec("-3.25"));
+        metrics.updated_at = scraped_at;
+
+        share.order_book = Some(OrderBook {
+            isin: isin.to_owned(),
+            bids: vec![level(1, "6.866", 1200, Some(3))],
+            asks: vec![level(1, "6.868", 900, Some(2)), level(2, "6.87", 500, None)],
+            updated_at: scraped_at,
+        });
+
+        share
+    }
+
+    pub(crate) async fn insert_isins(isins: &[&str], pool: &PgPool) {
+        for isin in isins {
+            query("INSERT INTO share_isins (isin, share_name, updated_at) VALUES ($1, $1, NOW())")
+                .bind(isin)
+                .execute(pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    pub(crate) async fn stored_share(isin: &str, pool: &PgPool) -> Share {
+        let query = ShareQuery::builder().isin(isin.to_owned()).build();
+        let mut shares = query_share_with(query, pool).await.unwrap();
+        assert_eq!(shares.len(), 1, "{}", isin);
+        shares.pop().unwrap()
+    }
+
+    fn assert_stored(stored: &Share, expected: &Share) {
+        let isin = &expected.price_data.isin;
+        let (s, e) = (&stored.share_details, &expected.share_details);
+        assert_eq!(s.id_strumento, e.id_strumento, "{}", isin);
+        assert_eq!(s.codice_alfanumerico, e.codice_alfanumerico, "{}", isin);
+
+        let (s, e) = (&stored.market_information, &expected.market_information);
+        assert_eq!(s.super_sector, e.super_sector, "{}", isin);
+        assert_eq!(s.mercato_segmento, e.mercato_segmento, "{}", isin);
+        assert_eq!(
+            s.capitalizzazione_di_mercato, e.capitalizzazione_di_mercato,
+            "{}",
+            isin
+        );
+        assert_eq!(s.lotto_minimo, e.lotto_minimo, "{}", isin);
+
+        let (s, e) = (&stored.price_data, &expected.price_data);
+        assert_eq!(s.fase_di_mercato, e.fase_di_mercato, "{}", isin);
+        assert_eq!(
+            s.prezzo_ultimo_contratto, e.prezzo_ultimo_contratto,
+            "{}",
+            isin
+        );
+        assert_eq!(s.var_percentuale, e.var_percentuale, "{}", isin);
+        assert_eq!(
+            s.data_ora_ultimo_contratto, e.data_ora_ultimo_contratto,
+            "{}",
+            isin
+        );
+        assert_eq!(s.quantita_totale, e.quantita_totale, "{}", isin);
+        assert_eq!(s.numero_contratti, e.numero_contratti, "{}", isin);
+        let (s_max, e_max) = (s.max_anno.as_ref().unwrap(), e.max_anno.as_ref().unwrap());
+        assert_eq!(
+            (s_max.price, s_max.date),
+            (e_max.price, e_max.date),
+            "{}",
+            isin
+        );
+        let (s_ref, e_ref) = (
+            s.prezzo_riferimento.as_ref().unwrap(),
+            e.prezzo_riferimento.as_ref().unwrap(),
+        );
+        assert_eq!(
+            (s_ref.price, s_ref.datetime),
+            (e_ref.price, e_ref.datetime),
+            "{}",
+            isin
+        );
+        assert_eq!(s.updated_at, e.updated_at, "{}", isin);
+
+        let (s, e) = (&stored.performance_metrics, &expected.performance_metrics);
+        assert_eq!(s.performance_1_mese, e.performance_1_mese, "{}", isin);
+        assert_eq!(s.performance_6_mesi, e.performance_6_mesi, "{}", isin);
+        assert_eq!(s.performance_1_anno, e.performance_1_anno, "{}", isin);
+    }
+
+    #[sqlx::test(migrator = "crate::migrations::MIGRATOR")]
+    async fn inserts_every_table(pool: PgPool) {
+        insert_isins(&[ENEL, GENERALI], &pool).await;
+        let scraped_at = now();
+        let shares = vec![
+            test_share(ENEL, "6.868", scraped_at),
+            test_share(GENERALI, "25.14", scraped_at),
+        ];
+
+        insert_share_batch(&shares, &pool).await.unwrap();
+
+        for expected in &shares {
+            let isin = &expected.price_data.isin;
+            assert_stored(&stored_share(isin, &pool).await, expected);
+
+            let book = query_latest_order_book(isin, &pool).await.unwrap().unwrap();
+            let expected_book = expected.order_book.as_ref().unwrap();
+            assert_eq!(book.bids, expected_book.bids);
+            assert_eq!(book.asks, expected_book.asks);
+            assert_eq!(book.updated_at, scraped_at);
+        }
+
+        let snapshots: Vec<(String, Option<Decimal>)> = sqlx::query_as(
+            "SELECT isin, prezzo_ultimo_contratto FROM price_snapshots ORDER BY isin",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            snapshots,
+            [
+                (GENERALI.to_owned(), Some(dec("25.14"))),
+                (ENEL.to_owned(), Some(dec("6.868")))
+            ]
+        );
+    }
+
+    // missing values keep the stored ones, the snapshots keep every scrape
+    #[sqlx::test(migrator = "crate::migrations::MIGRATOR")]
+    async fn upserts_existing_shares(pool: PgPool) {
+        insert_isins(&[ENEL], &pool).await;
+        let first = test_share(ENEL, "6.868", now() - chrono::Duration::minutes(15));
+        insert_share_batch(&[first], &pool).await.unwrap();
+
+        let mut second = test_share(ENEL, "6.9", now());
+        second.share_details.codice_alfanumerico = None;
+        second.market_information.super_sector = None;
+        second.order_book = None;
+        insert_share_batch(&[second], &pool).await.unwrap();
+
+        let stored = stored_share(ENEL, &pool).await;
+        assert_eq!(stored.price_data.prezzo_ultimo_contratto, Some(dec("6.9")));
+        assert_eq!(
+            stored.share_details.codice_alfanumerico.as_deref(),
+            Some("ENEL")
+        );
+        assert_eq!(
+            stored.market_information.super_sector.as_deref(),
+            Some("Utilities")
+        );
+
+        let snapshots: i64 = query_scalar("SELECT COUNT(*) FROM price_snapshots")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(snapshots, 2);
+        let book_snapshots: i64 =
+            query_scalar("SELECT COUNT(DISTINCT snapshot_at) FROM order_book_levels")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(book_snapshots, 1);
+    }
+
+    #[sqlx::test(migrator = "crate::migrations::MIGRATOR")]
+    async fn fails_the_whole_batch(pool: PgPool) {
+        insert_isins(&[ENEL], &pool).await;
+        let scraped_at = now();
+        let shares = [
+            test_share(ENEL, "6.868", scraped_at),
+            // not in share_isins
+            test_share(MEDIOBANCA, "15.2", scraped_at),
+        ];
+
+        assert!(insert_share_batch(&shares, &pool).await.is_err());
+
+        let stored: i64 = query_scalar("SELECT COUNT(*) FROM price_data")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(stored, 0);
+    }
+}
@@ -1,13 +1,23 @@
 use chrono::TimeDelta;
-use futures::{stream::FuturesUnordered, StreamExt};
-use scraper::{isins::types::ShareIsin, shares::Share};
+use futures::{stream::FuturesUnordered, FutureExt, StreamExt};
+use scraper::{
+    isins::types::ShareIsin,
+    shares::{MarketPhase, MarketSegment, Share},
+};
 use serde::Deserialize;
 use sqlx::query_file;
 use sqlx::{postgres::types::PgInterval, query_as, Pool, Postgres, QueryBuilder};
+use std::collections::HashSet;
 use tracing::{error, info, info_span, warn, Instrument};
 
 use crate::metrics::InsertionMetrics;
-use crate::utils::empty_string_as_none;
+use crate::order_books::insert_order_book;
+use crate::price_snapshots::insert_price_snapshot;
+use crate::share_batches::insert_share_batch;
+use crate::utils::{empty_string_as_none, push_condition};
+
+// see DbConfig::shares_batch_size
+pub const DEFAULT_SHARES_BATCH_SIZE: usize = 500;
 
 // IMPORTANT:
 // share queries are found at:
@@ -70,10 +80,10 @@ const SHARE_ISINS_WITH_INTERVAL: &str = r#"
         SELECT 
             si.isin,
             GREATEST(
-                COALESCE(sd.updated_at, '1970-01-01'::TIMESTAMP),
-                COALESCE(mi.updated_at, '1970-01-01'::TIMESTAMP),
-                COALESCE(pd.updated_at, '1970-01-01'::TIMESTAMP),
-                COALESCE(pm.updated_at, '1970-01-01'::TIMESTAMP)
+                COALESCE(sd.updated_at, '1970-01-01'::TIMESTAMPTZ),
+                COALESCE(mi.updated_at, '1970-01-01'::TIMESTAMPTZ),
+                COALESCE(pd.updated_at, '1970-01-01'::TIMESTAMPTZ),
+                COALESCE(pm.updated_at, '1970-01-01'::TIMESTAMPTZ)
             ) AS last_update
         FROM share_isins si
         LEFT JOIN share_details sd ON si.isin = sd.isin
@@ -98,11 +108,23 @@ pub struct ShareQuery {
     pub isin: Option<String>,
     #[serde(default, deserialize_with = "empty_string_as_none")]
     pub lang: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub fase_di_mercato: Option<MarketPhase>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub mercato_segmento: Option<MarketSegment>,
+    // index code, e.g. "FTSEMIB"
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub indice: Option<String>,
 }
 
 impl ShareQuery {
     pub fn new(isin: Option<String>, name: Option<String>, lang: Option<String>) -> Self {
-        Self { isin, name, lang }
+        Self {
+            isin,
+            name,
+            lang,
+            ..Default::default()
+        }
     }
     pub fn empty() -> Self {
         Self::default()
@@ -117,6 +139,9 @@ pub struct ShareQueryBuilder {
     pub name: Option<String>,
     pub isin: Option<String>,
     pub lang: Option<String>,
+    pub fase_di_mercato: Option<MarketPhase>,
+    pub mercato_segmento: Option<MarketSegment>,
+    pub indice: Option<String>,
 }
 impl ShareQueryBuilder {
     pub fn name(mut self, name: String) -> ShareQueryBuilder {
@@ -131,12 +156,27 @@ impl ShareQueryBuilder {
         self.lang = Some(lang);
         self
     }
+    pub fn fase_di_mercato(mut self, fase_di_mercato: MarketPhase) -> ShareQueryBuilder {
+        self.fase_di_mercato = Some(fase_di_mercato);
+        self
+    }
+    pub fn mercato_segmento(mut self, mercato_segmento: MarketSegment) -> ShareQueryBuilder {
+        self.mercato_segmento = Some(mercato_segmento);
+        self
+    }
+    pub fn indice(mut self, indice: String) -> ShareQueryBuilder {
+        self.indice = Some(indice);
+        self
+    }
 
     pub fn build(self) -> ShareQuery {
         ShareQuery {
             name: self.name,
             isin: self.isin,
             lang: self.lang,
+            fase_di_mercato: self.fase_di_mercato,
+            mercato_segmento: self.mercato_segmento,
+            indice: self.indice,
         }
     }
 }
@@ -147,33 +187,51 @@ pub async fn query_share_with(
 ) -> Result<Vec<Share>, sqlx::Error> {
     info!("Querying shares with {:?}", query);
     let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(INITIAL_SHARE_QUERY);
+    let mut has_conditions = false;
 
-    let query_as = match (query.name, query.isin, query.lang) {
-        (_, Some(isin), _) => query_builder
-            .push(" WHERE si.isin = ")
-            .push_bind(isin)
-            .build_query_as(),
-        (None, None, Some(lang)) => query_builder
-            .push(" WHERE si.isin ILIKE ")
-            .push_bind(lang)
-            .push(" || '%'")
-            .build_query_as(),
-        (Some(name), None, Some(lang)) => query_builder
-            .push(" WHERE si.isin ILIKE ")
-            .push_bind(lang)
-            .push(" || '%' AND si.share_name ILIKE '%' || ")
-            .push_bind(name)
-            .push(" || '%'")
-            .build_query_as(),
-        (Some(name), None, None) => query_builder
-            .push(" WHERE si.share_name ILIKE '%' || ")
-            .push_bind(name)
-            .push(" || '%'")
-            .build_query_as(),
-        (None, None, None) => query_builder.build_query_as(),
-    };
+    // an exact ISIN makes name and lang irrelevant
+    if let Some(isin) = query.isin {
+        push_condition(&mut query_builder, &mut has_conditions);
+        query_builder.push("si.isin = ").push_bind(isin);
+    } else {
+        if let Some(lang) = query.lang {
+            push_condition(&mut query_builder, &mut has_conditions);
+            query_builder
+                .push("si.isin ILIKE ")
+                .push_bind(lang)
+                .push(" || '%'");
+        }
+        if let Some(name) = query.name {
+            push_condition(&mut query_builder, &mut has_conditions);
+            query_builder
+                .push("si.share_name ILIKE '%' || ")
+                .push_bind(name)
+                .push(" || '%'");
+        }
+    }
+
+    if let Some(fase_di_mercato) = query.fase_di_mercato {
+        push_condition(&mut query_builder, &mut has_conditions);
+        query_builder
+            .push("pd.fase_di_mercato = ")
+            .push_bind(fase_di_mercato.to_string());
+    }
+    if let Some(mercato_segmento) = query.mercato_segmento {
+        push_condition(&mut query_builder, &mut has_conditions);
+        query_builder
+            .push("mi.mercato_segmento = ")
+            .push_bind(mercato_segmento.to_string());
+    }
+    // only current constituents
+    if let Some(indice) = query.indice {
+        push_condition(&mut query_builder, &mut has_conditions);
+        query_builder
+            .push("EXISTS (SELECT 1 FROM index_memberships im WHERE im.isin = si.isin AND im.valid_to IS NULL AND im.index_code = UPPER(")
+            .push_bind(indice)
+            .push("))");
+    }
 
-    let res = query_as.fetch_all(pool).await?;
+    let res = query_builder.build_query_as().fetch_all(pool).await?;
 
     if res.is_empty() {
         warn!("Found no share");
@@ -198,25 +256,85 @@ pub async fn get_shares_to_refresh(
         .await
 }
 
-pub async fn insert_all_shares(shares: Vec<Share>, pool: &Pool<Postgres>) -> InsertionMetrics {
+// a batch that fails is retried share by share, so that a bad share only fails
+// its own ISIN
+pub async fn insert_all_shares(
+    shares: Vec<Share>,
+    batch_size: usize,
+    pool: &Pool<Postgres>,
+) -> InsertionMetrics {
+    let share_num = shares.len() as i32;
+    let batches = unique_isin_batches(shares, batch_size.max(1));
+    let batch_num = batches.len();
+    let mut metrics = InsertionMetrics {
+        total: share_num,
+        ..Default::default()
+    };
+
+    info!(
+        "Inserting a total of {} Shares in {} batches",
+        share_num, batch_num
+    );
+
+    for (curr_idx, batch) in batches.into_iter().enumerate() {
+        info!(
+            "Inserting share batch {}/{} ({} shares)",
+            curr_idx + 1,
+            batch_num,
+            batch.len()
+        );
+
+        let res = insert_share_batch(&batch, pool)
+            .instrument(info_span!("inserting_share_batch"))
+            .await;
+        match res {
+            Ok(()) => metrics.successful += batch.len() as i32,
+            Err(e) => {
+                warn!(
+                    "Unable to insert batch of {} shares, inserting them one by one: {}",
+                    batch.len(),
+                    e
+                );
+                let batch_metrics = insert_shares_one_by_one(batch, pool).await;
+                metrics.successful += batch_metrics.successful;
+                metrics.failed_isins.extend(batch_metrics.failed_isins);
+            }
+        }
+    }
+
+    metrics
+}
+
+// one transaction per share
+pub async fn insert_shares_one_by_one(
+    shares: Vec<Share>,
+    pool: &Pool<Postgres>,
+) -> InsertionMetrics {
     let share_num = shares.len() as i32;
     let mut tasks = FuturesUnordered::new();
 
     info!("Inserting a total of {} Shares", share_num);
 
     for share in shares {
-        tasks.push(insert_share(share, pool).instrument(info_span!("inserting_share")));
+        let isin = share.share_id.isin.to_string();
+        tasks.push(
+            insert_share(share, pool)
+                .map(|res| (isin, res))
+                .instrument(info_span!("inserting_share")),
+        );
     }
 
     let mut curr_idx = 0;
     let mut successful_inserts = 0;
+    let mut failed_isins = Vec::new();
 
-    while let Some(res) = tasks.next().await {
+    while let Some((isin, res)) = tasks.next().await {
         curr_idx += 1;
         info!("Inserting share {}/{}", curr_idx, share_num);
 
         if let Err(e) = res {
-            error!("Unable to insert Share, {}", e);
+            error!("Unable to insert Share {}, {}", isin, e);
+            failed_isins.push(isin);
         } else {
             successful_inserts += 1;
         }
@@ -225,9 +343,36 @@ pub async fn insert_all_shares(shares: Vec<Share>, pool: &Pool<Postgres>) -> Ins
     InsertionMetrics {
         total: share_num,
         successful: successful_inserts,
+        failed_isins,
     }
 }
 
+// keeps the order of the shares, a repeated ISIN goes to a later batch
+fn unique_isin_batches(shares: Vec<Share>, batch_size: usize) -> Vec<Vec<Share>> {
+    let mut batches: Vec<(HashSet<String>, Vec<Share>)> = Vec::new();
+
+    for share in shares {
+        let isin = share.share_id.isin.to_string();
+        let first_free = batches
+            .iter()
+            .rposition(|(isins, _)| isins.contains(&isin))
+            .map_or(0, |i| i + 1);
+        let free_batch = batches[first_free..]
+            .iter_mut()
+            .find(|(_, batch)| batch.len() < batch_size);
+
+        match free_batch {
+            Some((isins, batch)) => {
+                isins.insert(isin);
+                batch.push(share);
+            }
+            None => batches.push((HashSet::from([isin]), vec![share])),
+        }
+    }
+
+    batches.into_iter().map(|(_, batch)| batch).collect()
+}
+
 pub async fn insert_share(share: Share, pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
     let mut tx = pool.begin().await?;
     let isin = share.share_id.isin;
@@ -236,6 +381,7 @@ pub async fn insert_share(share: Share, pool: &Pool<Postgres>) -> Result<(), sql
     let market_information = share.market_information;
     let price_data = share.price_data;
     let performance_metrics = share.performance_metrics;
+    let order_book = share.order_book;
 
     info!("Inserting ShareDetails for {}", isin);
     query_file!(
@@ -249,11 +395,20 @@ pub async fn insert_share(share: Share, pool: &Pool<Postgres>) -> Result<(), sql
     .await?;
 
     info!("Inserting MarketInformation for {}", isin);
+    let mercato_segmento = market_information
+        .mercato_segmento
+        .as_ref()
+        .map(|segment| segment.to_string());
+    if let Some(segment) = &mercato_segmento {
+        query_file!("./queries/share/insert_market_segment.sql", segment)
+            .execute(&mut *tx)
+            .await?;
+    }
     query_file!(
         "./queries/share/insert_market_info.sql",
         market_information.isin,
         market_information.super_sector,
-        market_information.mercato_segmento,
+        mercato_segmento,
         market_information.capitalizzazione_di_mercato,
         market_information.lotto_minimo,
         market_information.updated_at
@@ -262,10 +417,20 @@ pub async fn insert_share(share: Share, pool: &Pool<Postgres>) -> Result<(), sql
     .await?;
 
     info!("Inserting PriceData for {}", isin);
+    let fase_di_mercato = price_data
+        .fase_di_mercato
+        .as_ref()
+        .map(|phase| phase.to_string());
+    if let Some(phase) = &fase_di_mercato {
+        query_file!("./queries/share/insert_market_phase.sql", phase)
+            .execute(&mut *tx)
+            .await?;
+    }
+    insert_price_snapshot(&price_data, &mut tx).await?;
     query_file!(
         "./queries/share/insert_price_data.sql",
         price_data.isin,
-        price_data.fase_di_mercato,
+        fase_di_mercato,
         price_data.prezzo_ultimo_contratto,
         price_data.var_percentuale,
         price_data.var_assoluta,
@@ -295,6 +460,11 @@ pub async fn insert_share(share: Share, pool: &Pool<Postgres>) -> Result<(), sql
     .execute(&mut *tx)
     .await?;
 
+    if let Some(order_book) = order_book.as_ref().filter(|book| !book.is_empty()) {
+        info!("Inserting OrderBook for {}", isin);
+        insert_order_book(order_book, &mut tx).await?;
+    }
+
     info!("Inserting PerformanceMetrics for {}", isin);
     query_file!(
         "./queries/share/insert_performance_metrics.sql",
@@ -314,3 +484,104 @@ pub async fn insert_share(share: Share, pool: &Pool<Postgres>) -> Result<(), sql
     info!("Successfully commited transition for {}", isin);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use sqlx::{query_scalar, PgPool};
+
+    use super::*;
+    use crate::share_batches::tests::{
+        dec, insert_isins, now, stored_share, test_share, ENEL, GENERALI, MEDIOBANCA,
+    };
+
+    fn isins(batches: &[Vec<Share>]) -> Vec<Vec<String>> {
+        batches
+            .iter()
+            .map(|batch| batch.iter().map(|s| s.share_id.isin.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn splits_batches_by_size() {
+        let at = now();
+        let shares = [ENEL, GENERALI, MEDIOBANCA]
+            .map(|isin| test_share(isin, "1", at))
+            .into();
+
+        let batches = unique_isin_batches(shares, 2);
+
+        assert_eq!(isins(&batches), [vec![ENEL, GENERALI], vec![MEDIOBANCA]]);
+    }
+
+    #[test]
+    fn moves_repeated_isins_to_later_batches() {
+        let at = now();
+        let shares = [ENEL, GENERALI, ENEL, ENEL, MEDIOBANCA]
+            .map(|isin| test_share(isin, "1", at))
+            .into();
+
+        let batches = unique_isin_batches(shares, 3);
+
+        assert_eq!(
+            isins(&batches),
+            [vec![ENEL, GENERALI, MEDIOBANCA], vec![ENEL], vec![ENEL],]
+        );
+    }
+
+    #[sqlx::test(migrator = "crate::migrations::MIGRATOR")]
+    async fn inserts_repeated_isins_in_order(pool: PgPool) {
+        insert_isins(&[ENEL, GENERALI], &pool).await;
+        let at = now();
+        let shares = vec![
+            test_share(ENEL, "6.8", at - Duration::minutes(1)),
+            test_share(GENERALI, "25.1", at),
+            test_share(ENEL, "6.9", at),
+        ];
+
+        let metrics = insert_all_shares(shares, 2, &pool).await;
+
+        assert_eq!((metrics.total, metrics.successful), (3, 3));
+        assert!(metrics.failed_isins.is_empty());
+        let stored = stored_share(ENEL, &pool).await;
+        assert_eq!(stored.price_data.prezzo_ultimo_contratto, Some(dec("6.9")));
+        let snapshots: i64 = query_scalar("SELECT COUNT(*) FROM price_snapshots WHERE isin = $1")
+            .bind(ENEL)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(snapshots, 2);
+    }
+
+    #[sqlx::test(migrator = "crate::migrations::MIGRATOR")]
+    async fn fails_only_the_bad_share_of_a_batch(pool: PgPool) {
+        // MEDIOBANCA isn't in share_isins, so its rows break the foreign keys
+        insert_isins(&[ENEL, GENERALI], &pool).await;
+        let at = now();
+        let shares = vec![
+            test_share(ENEL, "6.8", at - Duration::minutes(1)),
+            test_share(MEDIOBANCA, "15.2", at),
+            test_share(GENERALI, "25.1", at),
+            test_share(ENEL, "6.9", at),
+        ];
+
+        let metrics = insert_all_shares(shares, 10, &pool).await;
+
+        assert_eq!((metrics.total, metrics.successful), (4, 3));
+        assert_eq!(metrics.failed_isins, [MEDIOBANCA]);
+        assert_eq!(
+            stored_share(ENEL, &pool)
+                .await
+                .price_data
+                .prezzo_ultimo_contratto,
+            Some(dec("6.9"))
+        );
+        assert_eq!(
+            stored_share(GENERALI, &pool)
+                .await
+                .price_data
+                .prezzo_ultimo_contratto,
+            Some(dec("25.1"))
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+use scraper::trades::types::Trade;
+use sqlx::{query_as, query_file, Pool, Postgres};
+use tracing::{error, info};
+
+use crate::metrics::InsertionMetrics;
+
+const TRADES_BATCH_SIZE: usize = 1000;
+
+// trades already in the table (from a previous refresh) are skipped
+pub async fn insert_all_trades(trades: Vec<Trade>, pool: &Pool<Postgres>) -> InsertionMetrics {
+    let trade_num = trades.len() as i32;
+    let mut successful_inserts = 0;
+    let mut new_trades = 0;
+
+    info!("Inserting a total of {} trades", trade_num);
+
+    for batch in trades.chunks(TRADES_BATCH_SIZE) {
+        match insert_trades(batch, pool).await {
+            Ok(inserted) => {
+                successful_inserts += batch.len() as i32;
+                new_trades += inserted;
+            }
+            Err(e) => error!("Unable to insert {} trades, {}", batch.len(), e),
+        }
+    }
+    info!("Inserted {} new trades", new_trades);
+
+    InsertionMetrics {
+        total: trade_num,
+        successful: successful_inserts,
+        ..Default::default()
+    }
+}
+
+pub async fn insert_trades(trades: &[Trade], pool: &Pool<Postgres>) -> Result<u64, sqlx::Error> {
+    let isins: Vec<String> = trades.iter().map(|t| t.isin.clone()).collect();
+    let traded_ats: Vec<DateTime<Utc>> = trades.iter().map(|t| t.traded_at).collect();
+    let prices: Vec<_> = trades.iter().map(|t| t.price).collect();
+    let quantities: Vec<i64> = trades.iter().map(|t| t.quantity as i64).collect();
+    let seqs: Vec<i32> = trades.iter().map(|t| t.seq).collect();
+
+    let res = query_file!(
+        "./queries/trade/insert_trades.sql",
+        &isins,
+        &traded_ats,
+        &prices,
+        &quantities,
+        &seqs
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(res.rows_affected())
+}
+
+pub async fn query_trades(
+    isin: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    pool: &Pool<Postgres>,
+) -> Result<Vec<Trade>, sqlx::Error> {
+    info!("Querying trades for {} between {} and {}", isin, from, to);
+    query_as(
+        r#"
+        SELECT isin, traded_at, price, quantity, seq
+        FROM trades
+        WHERE isin = $1 AND traded_at >= $2 AND traded_at < $3
+        ORDER BY traded_at, seq
+        "#,
+    )
+    .bind(isin)
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+}
@@ -1,4 +1,5 @@
 use serde::{de, Deserialize, Deserializer};
+use sqlx::{Postgres, QueryBuilder};
 use std::{fmt, str::FromStr};
 
 // from axum docs
@@ -14,3 +15,16 @@ where
         Some(s) => FromStr::from_str(s).map_err(de::Error::custom).map(Some),
     }
 }
+
+// pushes " WHERE " for the first condition and " AND " for the following ones
+pub(crate) fn push_condition(
+    query_builder: &mut QueryBuilder<Postgres>,
+    has_conditions: &mut bool,
+) {
+    if *has_conditions {
+        query_builder.push(" AND ");
+    } else {
+        query_builder.push(" WHERE ");
+        *has_conditions = true;
+    }
+}
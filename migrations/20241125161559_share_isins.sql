@@ -1,5 +0,0 @@
-CREATE TABLE IF NOT EXISTS share_isins (
-  isin VARCHAR(12) PRIMARY KEY,
-  share_name VARCHAR(50)
-);
-
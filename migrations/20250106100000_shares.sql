@@ -0,0 +1,194 @@
+-- consolidated schema of the share tables; every statement is a no-op on a
+-- database that is already up to date, the guarded blocks upgrade databases
+-- created by the old (destructive) migrations in place
+
+CREATE TABLE IF NOT EXISTS share_isins (
+  isin VARCHAR(12) PRIMARY KEY NOT NULL,
+  share_name VARCHAR(50) NOT NULL,
+  updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS market_phases (
+  phase VARCHAR(50) PRIMARY KEY
+);
+
+INSERT INTO market_phases (phase) VALUES
+  ('Pre-Apertura'),
+  ('Asta di Apertura'),
+  ('Negoziazione Continua'),
+  ('Asta di Volatilità'),
+  ('Asta di Chiusura'),
+  ('Trading at Last'),
+  ('Chiusura'),
+  ('Sospeso')
+ON CONFLICT DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS market_segments (
+  segment VARCHAR(50) PRIMARY KEY
+);
+
+INSERT INTO market_segments (segment) VALUES
+  ('Blue Chip'),
+  ('STAR'),
+  ('Standard'),
+  ('Euronext Growth Milan'),
+  ('MIV')
+ON CONFLICT DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS share_details (
+  isin VARCHAR(12) PRIMARY KEY,
+  id_strumento DOUBLE PRECISION NULL,
+  codice_alfanumerico VARCHAR(50) NULL,
+  updated_at TIMESTAMPTZ NOT NULL,
+  FOREIGN KEY (isin) REFERENCES share_isins(isin)
+);
+
+CREATE TABLE IF NOT EXISTS market_information (
+  isin VARCHAR(12) PRIMARY KEY,
+  super_sector VARCHAR(50) NULL,
+  mercato_segmento VARCHAR(50) NULL,
+  capitalizzazione_di_mercato NUMERIC NULL,
+  lotto_minimo DOUBLE PRECISION NULL,
+  updated_at TIMESTAMPTZ NOT NULL,
+  FOREIGN KEY (isin) REFERENCES share_isins(isin),
+  FOREIGN KEY (mercato_segmento) REFERENCES market_segments(segment)
+);
+
+CREATE TABLE IF NOT EXISTS price_data (
+  isin VARCHAR(12) PRIMARY KEY,
+  fase_di_mercato VARCHAR(50) NULL,
+  prezzo_ultimo_contratto NUMERIC NULL,
+  var_percentuale NUMERIC NULL,
+  var_assoluta NUMERIC NULL,
+  pr_medio_progr NUMERIC NULL,
+  data_ora_ultimo_contratto TIMESTAMPTZ NULL,
+  quantita_ultimo NUMERIC NULL,
+  quantita_totale NUMERIC NULL,
+  numero_contratti INT NULL,
+  controvalore NUMERIC NULL,
+  max_oggi NUMERIC NULL,
+  max_anno NUMERIC NULL,
+  max_anno_date DATE NULL,
+  min_oggi NUMERIC NULL,
+  min_anno NUMERIC NULL,
+  min_anno_date DATE NULL,
+  chiusura_precedente NUMERIC NULL,
+  prezzo_riferimento NUMERIC NULL,
+  data_ora_prezzo_rifermento TIMESTAMPTZ NULL,
+  prezzo_ufficiale NUMERIC NULL,
+  data_prezzo_ufficiale DATE NULL,
+  apertura_odierna NUMERIC NULL,
+  updated_at TIMESTAMPTZ NOT NULL,
+  FOREIGN KEY (isin) REFERENCES share_isins(isin),
+  FOREIGN KEY (fase_di_mercato) REFERENCES market_phases(phase)
+);
+
+CREATE TABLE IF NOT EXISTS performance_metrics (
+  isin VARCHAR(12) PRIMARY KEY,
+  performance_1_mese NUMERIC NULL,
+  performance_6_mesi NUMERIC NULL,
+  performance_1_anno NUMERIC NULL,
+  updated_at TIMESTAMPTZ NOT NULL,
+  FOREIGN KEY (isin) REFERENCES share_isins(isin)
+);
+
+-- prices and variations were stored as DOUBLE PRECISION, the (already rounded)
+-- values are kept
+DO $$
+DECLARE
+  tbl TEXT;
+  col TEXT;
+BEGIN
+  FOR tbl, col IN
+    SELECT table_name, column_name FROM information_schema.columns
+    WHERE table_schema = current_schema()
+      AND table_name IN ('price_data', 'performance_metrics')
+      AND data_type = 'double precision'
+  LOOP
+    EXECUTE format(
+      'ALTER TABLE %I ALTER COLUMN %I TYPE NUMERIC USING %I::NUMERIC', tbl, col, col
+    );
+  END LOOP;
+
+  IF EXISTS (
+    SELECT FROM information_schema.columns
+    WHERE table_schema = current_schema()
+      AND table_name = 'market_information'
+      AND column_name = 'capitalizzazione_di_mercato'
+      AND data_type = 'double precision'
+  ) THEN
+    ALTER TABLE market_information
+      ALTER COLUMN capitalizzazione_di_mercato TYPE NUMERIC USING capitalizzazione_di_mercato::NUMERIC;
+  END IF;
+END $$;
+
+-- timestamps were stored without time zone: exchange times as Italian wall-clock
+-- times, updated_at as UTC
+DO $$
+DECLARE
+  tbl TEXT;
+  col TEXT;
+BEGIN
+  FOR tbl, col IN
+    SELECT table_name, column_name FROM information_schema.columns
+    WHERE table_schema = current_schema()
+      AND table_name IN (
+        'share_isins', 'share_details', 'market_information', 'price_data', 'performance_metrics'
+      )
+      AND data_type = 'timestamp without time zone'
+  LOOP
+    EXECUTE format(
+      'ALTER TABLE %I ALTER COLUMN %I TYPE TIMESTAMPTZ USING %I AT TIME ZONE %L',
+      tbl, col, col, CASE WHEN col = 'updated_at' THEN 'UTC' ELSE 'Europe/Rome' END
+    );
+  END LOOP;
+END $$;
+
+-- the labels scraped before the lookup tables existed are normalized to the ones
+-- used by the scraper, unknown ones are added
+DO $$
+BEGIN
+  IF NOT EXISTS (
+    SELECT FROM pg_constraint
+    WHERE conrelid = 'price_data'::REGCLASS
+      AND confrelid = 'market_phases'::REGCLASS
+  ) THEN
+    UPDATE price_data pd
+    SET fase_di_mercato = mp.phase
+    FROM market_phases mp
+    WHERE LOWER(TRIM(pd.fase_di_mercato)) = LOWER(mp.phase);
+
+    INSERT INTO market_phases (phase)
+    SELECT DISTINCT fase_di_mercato FROM price_data
+    WHERE fase_di_mercato IS NOT NULL
+    ON CONFLICT DO NOTHING;
+
+    ALTER TABLE price_data
+      ADD FOREIGN KEY (fase_di_mercato) REFERENCES market_phases(phase);
+  END IF;
+
+  IF NOT EXISTS (
+    SELECT FROM pg_constraint
+    WHERE conrelid = 'market_information'::REGCLASS
+      AND confrelid = 'market_segments'::REGCLASS
+  ) THEN
+    UPDATE market_information
+    SET mercato_segmento = CASE
+      WHEN mercato_segmento ~* '(^|/)\s*(euronext )?star( milan)?\s*($|/)' THEN 'STAR'
+      WHEN mercato_segmento ~* '(^|/)\s*blue ?chip\s*($|/)' THEN 'Blue Chip'
+      WHEN mercato_segmento ~* '(^|/)\s*standard\s*($|/)' THEN 'Standard'
+      WHEN mercato_segmento ~* '(^|/)\s*(egm|aim|aim italia|euronext growth milan)\s*($|/)' THEN 'Euronext Growth Milan'
+      WHEN mercato_segmento ~* '(^|/)\s*(euronext )?miv( milan)?\s*($|/)' THEN 'MIV'
+      ELSE TRIM(mercato_segmento)
+    END
+    WHERE mercato_segmento IS NOT NULL;
+
+    INSERT INTO market_segments (segment)
+    SELECT DISTINCT mercato_segmento FROM market_information
+    WHERE mercato_segmento IS NOT NULL
+    ON CONFLICT DO NOTHING;
+
+    ALTER TABLE market_information
+      ADD FOREIGN KEY (mercato_segmento) REFERENCES market_segments(segment);
+  END IF;
+END $$;
@@ -0,0 +1,68 @@
+CREATE TABLE IF NOT EXISTS company_profiles (
+  isin VARCHAR(12) PRIMARY KEY,
+  ragione_sociale VARCHAR(255) NULL,
+  indirizzo VARCHAR(255) NULL,
+  sito_web VARCHAR(255) NULL,
+  descrizione TEXT NULL,
+  azioni_in_circolazione BIGINT NULL,
+  flottante NUMERIC NULL,
+  data_quotazione DATE NULL,
+  updated_at TIMESTAMPTZ NOT NULL,
+  FOREIGN KEY (isin) REFERENCES share_isins(isin)
+);
+
+CREATE TABLE IF NOT EXISTS dividends (
+  isin VARCHAR(12) NOT NULL,
+  ex_date DATE NOT NULL,
+  payment_date DATE NULL,
+  amount NUMERIC NOT NULL,
+  dividend_type VARCHAR(50) NOT NULL,
+  updated_at TIMESTAMPTZ NOT NULL,
+  PRIMARY KEY (isin, ex_date, dividend_type),
+  FOREIGN KEY (isin) REFERENCES share_isins(isin)
+);
+
+CREATE INDEX IF NOT EXISTS dividends_upcoming_idx ON dividends (ex_date, payment_date);
+
+CREATE TABLE IF NOT EXISTS press_releases (
+  isin VARCHAR(12) NOT NULL,
+  link VARCHAR(512) NOT NULL,
+  title TEXT NOT NULL,
+  published_at TIMESTAMPTZ NOT NULL,
+  body TEXT NULL,
+  updated_at TIMESTAMPTZ NOT NULL,
+  search_vector TSVECTOR GENERATED ALWAYS AS (
+    setweight(to_tsvector('italian', title), 'A') ||
+    setweight(to_tsvector('italian', COALESCE(body, '')), 'B')
+  ) STORED,
+  PRIMARY KEY (isin, link),
+  FOREIGN KEY (isin) REFERENCES share_isins(isin)
+);
+
+CREATE INDEX IF NOT EXISTS press_releases_search_idx ON press_releases USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS press_releases_published_at_idx ON press_releases (isin, published_at);
+
+CREATE TABLE IF NOT EXISTS indices (
+  code VARCHAR(20) PRIMARY KEY,
+  name VARCHAR(100) NOT NULL,
+  valore NUMERIC NULL,
+  var_percentuale NUMERIC NULL,
+  var_assoluta NUMERIC NULL,
+  updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS index_memberships (
+  index_code VARCHAR(20) NOT NULL,
+  isin VARCHAR(12) NOT NULL,
+  valid_from TIMESTAMPTZ NOT NULL,
+  valid_to TIMESTAMPTZ NULL,
+  PRIMARY KEY (index_code, isin, valid_from),
+  FOREIGN KEY (index_code) REFERENCES indices(code),
+  FOREIGN KEY (isin) REFERENCES share_isins(isin),
+  CHECK (valid_to IS NULL OR valid_to > valid_from)
+);
+
+-- a share has at most one open membership per index
+CREATE UNIQUE INDEX IF NOT EXISTS index_memberships_current_idx
+  ON index_memberships (index_code, isin)
+  WHERE valid_to IS NULL;
@@ -0,0 +1,160 @@
+-- the snapshot tables are partitioned by (UTC) month of their snapshot time; the
+-- maintenance job creates the partitions ahead of time, rows without one land in
+-- the default partition and are moved when their month gets created
+
+-- creates the <parent>_pYYYYMM partition of `month`, false if it already exists
+CREATE OR REPLACE FUNCTION create_monthly_partition(parent TEXT, key TEXT, month DATE)
+RETURNS BOOLEAN AS $$
+DECLARE
+  partition TEXT := format('%s_p%s', parent, to_char(month, 'YYYYMM'));
+  starts_at TIMESTAMPTZ := date_trunc('month', month::TIMESTAMP) AT TIME ZONE 'UTC';
+  ends_at TIMESTAMPTZ := (date_trunc('month', month::TIMESTAMP) + INTERVAL '1 month') AT TIME ZONE 'UTC';
+BEGIN
+  IF to_regclass(partition) IS NOT NULL THEN
+    RETURN FALSE;
+  END IF;
+
+  EXECUTE format(
+    'CREATE TABLE %I (LIKE %I INCLUDING DEFAULTS INCLUDING CONSTRAINTS)',
+    partition, parent
+  );
+  EXECUTE format(
+    'WITH moved AS (DELETE FROM %I WHERE %I >= $1 AND %I < $2 RETURNING *)
+     INSERT INTO %I SELECT * FROM moved',
+    parent || '_default', key, key, partition
+  ) USING starts_at, ends_at;
+  EXECUTE format(
+    'ALTER TABLE %I ATTACH PARTITION %I FOR VALUES FROM (%L) TO (%L)',
+    parent, partition, starts_at, ends_at
+  );
+
+  RETURN TRUE;
+END;
+$$ LANGUAGE plpgsql;
+
+-- drops the monthly partitions of `parent` ending before `before` and deletes the
+-- older rows of the default partition, returns how many partitions were dropped
+CREATE OR REPLACE FUNCTION drop_monthly_partitions(parent TEXT, key TEXT, before DATE)
+RETURNS INT AS $$
+DECLARE
+  partition TEXT;
+  dropped INT := 0;
+BEGIN
+  FOR partition IN
+    SELECT c.relname
+    FROM pg_inherits i
+    JOIN pg_class c ON c.oid = i.inhrelid
+    WHERE i.inhparent = parent::REGCLASS AND c.relname ~ '_p[0-9]{6}$'
+  LOOP
+    IF to_date(right(partition, 6), 'YYYYMM') + INTERVAL '1 month' <= before THEN
+      EXECUTE format('DROP TABLE %I', partition);
+      dropped := dropped + 1;
+    END IF;
+  END LOOP;
+
+  EXECUTE format('DELETE FROM %I WHERE %I < $1', parent || '_default', key)
+    USING before::TIMESTAMP AT TIME ZONE 'UTC';
+
+  RETURN dropped;
+END;
+$$ LANGUAGE plpgsql;
+
+-- every scraped price_data row, price_data only keeps the latest values
+CREATE TABLE IF NOT EXISTS price_snapshots (
+  isin VARCHAR(12) NOT NULL,
+  -- when the page was scraped, the exchange timestamp is data_ora_ultimo_contratto
+  scraped_at TIMESTAMPTZ NOT NULL,
+  fase_di_mercato VARCHAR(50) NULL,
+  prezzo_ultimo_contratto NUMERIC NULL,
+  var_percentuale NUMERIC NULL,
+  var_assoluta NUMERIC NULL,
+  pr_medio_progr NUMERIC NULL,
+  data_ora_ultimo_contratto TIMESTAMPTZ NULL,
+  quantita_ultimo NUMERIC NULL,
+  quantita_totale NUMERIC NULL,
+  numero_contratti INT NULL,
+  controvalore NUMERIC NULL,
+  max_oggi NUMERIC NULL,
+  max_anno NUMERIC NULL,
+  max_anno_date DATE NULL,
+  min_oggi NUMERIC NULL,
+  min_anno NUMERIC NULL,
+  min_anno_date DATE NULL,
+  chiusura_precedente NUMERIC NULL,
+  prezzo_riferimento NUMERIC NULL,
+  data_ora_prezzo_rifermento TIMESTAMPTZ NULL,
+  prezzo_ufficiale NUMERIC NULL,
+  data_prezzo_ufficiale DATE NULL,
+  apertura_odierna NUMERIC NULL,
+  PRIMARY KEY (isin, scraped_at),
+  FOREIGN KEY (isin) REFERENCES share_isins(isin),
+  FOREIGN KEY (fase_di_mercato) REFERENCES market_phases(phase)
+) PARTITION BY RANGE (scraped_at);
+
+CREATE TABLE IF NOT EXISTS price_snapshots_default PARTITION OF price_snapshots DEFAULT;
+
+CREATE INDEX IF NOT EXISTS price_snapshots_exchange_time_idx
+  ON price_snapshots (isin, data_ora_ultimo_contratto);
+-- bars are built for all shares of a day
+CREATE INDEX IF NOT EXISTS price_snapshots_last_trade_idx
+  ON price_snapshots (data_ora_ultimo_contratto);
+
+CREATE TABLE IF NOT EXISTS order_book_levels (
+  isin VARCHAR(12) NOT NULL,
+  snapshot_at TIMESTAMPTZ NOT NULL,
+  side VARCHAR(3) NOT NULL CHECK (side IN ('bid', 'ask')),
+  level SMALLINT NOT NULL,
+  price NUMERIC NOT NULL,
+  quantity BIGINT NOT NULL,
+  orders INT NULL,
+  PRIMARY KEY (isin, snapshot_at, side, level),
+  FOREIGN KEY (isin) REFERENCES share_isins(isin)
+) PARTITION BY RANGE (snapshot_at);
+
+CREATE TABLE IF NOT EXISTS order_book_levels_default PARTITION OF order_book_levels DEFAULT;
+
+SELECT create_monthly_partition('price_snapshots', 'scraped_at', month::DATE)
+FROM generate_series(
+  date_trunc('month', COALESCE(
+    (SELECT MIN(scraped_at) FROM price_snapshots_default), NOW()
+  ) AT TIME ZONE 'UTC'),
+  date_trunc('month', NOW() AT TIME ZONE 'UTC') + INTERVAL '2 months',
+  INTERVAL '1 month'
+) month;
+
+SELECT create_monthly_partition('order_book_levels', 'snapshot_at', month::DATE)
+FROM generate_series(
+  date_trunc('month', COALESCE(
+    (SELECT MIN(snapshot_at) FROM order_book_levels_default), NOW()
+  ) AT TIME ZONE 'UTC'),
+  date_trunc('month', NOW() AT TIME ZONE 'UTC') + INTERVAL '2 months',
+  INTERVAL '1 month'
+) month;
+
+CREATE TABLE IF NOT EXISTS trades (
+  isin VARCHAR(12) NOT NULL,
+  traded_at TIMESTAMPTZ NOT NULL,
+  price NUMERIC NOT NULL,
+  quantity BIGINT NOT NULL,
+  seq INT NOT NULL,
+  PRIMARY KEY (isin, traded_at, price, quantity, seq),
+  FOREIGN KEY (isin) REFERENCES share_isins(isin)
+);
+
+-- one bar per share and trading day, built from price_snapshots
+CREATE TABLE IF NOT EXISTS daily_bars (
+  isin VARCHAR(12) NOT NULL,
+  -- Italian date of the trading day
+  trade_date DATE NOT NULL,
+  open NUMERIC NULL,
+  high NUMERIC NULL,
+  low NUMERIC NULL,
+  -- official price, the last price if it wasn't published yet
+  close NUMERIC NULL,
+  volume NUMERIC NULL,
+  turnover NUMERIC NULL,
+  trades INT NULL,
+  finalized_at TIMESTAMPTZ NOT NULL,
+  PRIMARY KEY (isin, trade_date),
+  FOREIGN KEY (isin) REFERENCES share_isins(isin)
+);
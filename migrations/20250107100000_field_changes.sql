@@ -0,0 +1,54 @@
+-- values overwritten by the share upserts, one row per changed field; the first
+-- value of a field isn't a change and isn't logged
+CREATE TABLE IF NOT EXISTS field_changes (
+  id BIGSERIAL PRIMARY KEY,
+  isin VARCHAR(12) NOT NULL,
+  table_name VARCHAR(50) NOT NULL,
+  field VARCHAR(50) NOT NULL,
+  -- as text, NULL when the field had no value
+  old_value TEXT NULL,
+  new_value TEXT NULL,
+  -- updated_at of the scrape that changed the value
+  changed_at TIMESTAMPTZ NOT NULL,
+  FOREIGN KEY (isin) REFERENCES share_isins(isin)
+);
+
+CREATE INDEX IF NOT EXISTS field_changes_timeline_idx ON field_changes (isin, changed_at);
+
+-- logs the fields passed as trigger arguments that differ between OLD and NEW,
+-- the rows must have isin and updated_at columns
+CREATE OR REPLACE FUNCTION log_field_changes()
+RETURNS TRIGGER AS $$
+DECLARE
+  old_row JSONB := to_jsonb(OLD);
+  new_row JSONB := to_jsonb(NEW);
+  field TEXT;
+BEGIN
+  FOREACH field IN ARRAY TG_ARGV LOOP
+    IF old_row -> field IS DISTINCT FROM new_row -> field THEN
+      INSERT INTO field_changes (isin, table_name, field, old_value, new_value, changed_at)
+      VALUES (NEW.isin, TG_TABLE_NAME, field, old_row ->> field, new_row ->> field, NEW.updated_at);
+    END IF;
+  END LOOP;
+
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS share_details_changes ON share_details;
+CREATE TRIGGER share_details_changes
+  AFTER UPDATE ON share_details
+  FOR EACH ROW
+  WHEN (OLD.* IS DISTINCT FROM NEW.*)
+  EXECUTE FUNCTION log_field_changes('id_strumento', 'codice_alfanumerico');
+
+-- capitalizzazione_di_mercato and the performance_metrics fields move with the
+-- price on every scrape, logging them would mostly record price ticks
+DROP TRIGGER IF EXISTS market_information_changes ON market_information;
+CREATE TRIGGER market_information_changes
+  AFTER UPDATE ON market_information
+  FOR EACH ROW
+  WHEN (OLD.* IS DISTINCT FROM NEW.*)
+  EXECUTE FUNCTION log_field_changes(
+    'super_sector', 'mercato_segmento', 'lotto_minimo'
+  );
@@ -0,0 +1,8 @@
+-- the (Italian) day up to which each snapshot table was downsampled, the
+-- maintenance job only scans the snapshots between it and the new cutoff
+CREATE TABLE IF NOT EXISTS snapshot_downsampling (
+  table_name VARCHAR(50) PRIMARY KEY,
+  -- snapshots before the start of this day keep one per day
+  downsampled_until DATE NOT NULL,
+  updated_at TIMESTAMPTZ NOT NULL
+);
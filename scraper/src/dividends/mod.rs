@@ -0,0 +1,105 @@
+use futures::{stream::FuturesUnordered, StreamExt};
+use once_cell::sync::Lazy;
+use scraper::{ElementRef, Html, Selector};
+use tracing::{debug, info, info_span, warn, Instrument};
+use types::{Dividend, DividendColumns};
+
+use crate::{
+    errors::{ScraperResult, ScrapingError},
+    get_page_text,
+    isins::types::ShareIsin,
+    metrics::{ScrapingMetrics, WithMetrics},
+};
+
+pub mod types;
+
+static TABLE_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("table").unwrap());
+static ROW_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("tr").unwrap());
+static HEADER_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("th").unwrap());
+static CELL_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("td").unwrap());
+
+pub async fn scrape_all_dividends(share_isins: Vec<ShareIsin>) -> WithMetrics<Vec<Dividend>> {
+    let mut metrics = ScrapingMetrics::empty();
+    metrics.total = share_isins.len() as i32;
+    let mut tasks = FuturesUnordered::new();
+
+    for share_isin in share_isins.iter() {
+        let isin_str = share_isin.isin.to_string();
+        tasks.push(
+            scrape_dividends(share_isin)
+                .instrument(info_span!("scraping_dividends", isin = isin_str)),
+        );
+    }
+
+    let mut res: Vec<Dividend> = Vec::new();
+    while let Some(result) = tasks.next().await {
+        match result {
+            Ok(dividends) => {
+                metrics.successful += 1;
+                res.extend(dividends);
+            }
+            Err(e) => metrics.errors.update(e),
+        }
+    }
+    info!("Scraped a total of {} dividends.", res.len());
+
+    WithMetrics::new(res, metrics)
+}
+
+pub async fn scrape_dividends(share_isin: &ShareIsin) -> ScraperResult<Vec<Dividend>> {
+    let url = format!(
+        "https://www.borsaitaliana.it/borsa/azioni/dividendi.html?isin={}&lang=it",
+        share_isin.isin
+    );
+
+    let res_txt = get_page_text(url)
+        .instrument(info_span!("fetching_page"))
+        .await?;
+
+    parse_page(&res_txt, &share_isin.isin.to_string())
+}
+
+// shares that never paid a dividend have no table, which isn't an error
+pub fn parse_page(res_txt: &str, isin: &str) -> ScraperResult<Vec<Dividend>> {
+    debug!("Parsing dividends page");
+
+    let doc = Html::parse_document(res_txt);
+    let Some((table, columns)) = doc.select(&TABLE_SELECTOR).find_map(|table| {
+        let headers: Vec<String> = table
+            .select(&HEADER_SELECTOR)
+            .map(|th| th.text().collect::<String>().trim().to_lowercase())
+            .collect();
+        DividendColumns::from_headers(&headers).map(|columns| (table, columns))
+    }) else {
+        debug!("No dividend table found");
+        return Ok(Vec::new());
+    };
+
+    let mut dividends = Vec::new();
+    for row in table.select(&ROW_SELECTOR) {
+        let cells: Vec<ElementRef> = row.select(&CELL_SELECTOR).collect();
+        if cells.is_empty() {
+            continue;
+        }
+
+        match Dividend::from_cells(&cells, &columns, isin) {
+            // a repeated row would make the upsert touch the same row twice
+            Ok(dividend)
+                if dividends
+                    .iter()
+                    .any(|d: &Dividend| d.same_payout(&dividend)) =>
+            {
+                debug!("Skipping repeated dividend {:?}", dividend)
+            }
+            Ok(dividend) => dividends.push(dividend),
+            Err(e) => warn!("Invalid dividend row: {:?}", e),
+        }
+    }
+
+    if dividends.is_empty() && table.select(&CELL_SELECTOR).next().is_some() {
+        return Err(ScrapingError::ParsingErr);
+    }
+    debug!("Found {} dividends", dividends.len());
+
+    Ok(dividends)
+}
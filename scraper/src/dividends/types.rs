@@ -0,0 +1,97 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use scraper::ElementRef;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::{ScraperResult, ScrapingError},
+    shares::{numbers::parse_number, parsers::parse_date},
+};
+
+// used when the page doesn't say which kind of dividend it is
+const DEFAULT_DIVIDEND_TYPE: &str = "ordinario";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Dividend {
+    pub isin: String,
+    pub ex_date: NaiveDate,
+    pub payment_date: Option<NaiveDate>,
+    pub amount: Decimal,
+    // e.g. "ordinario", "straordinario", "acconto", "saldo"
+    pub dividend_type: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+// column positions of the dividend table, found from its header
+pub(super) struct DividendColumns {
+    ex_date: usize,
+    payment_date: Option<usize>,
+    amount: usize,
+    dividend_type: Option<usize>,
+}
+
+impl DividendColumns {
+    // "Data Stacco | Data Pagamento | Dividendo | Divisa | Tipo"
+    pub(super) fn from_headers(headers: &[String]) -> Option<Self> {
+        let find = |terms: &[&str]| {
+            headers
+                .iter()
+                .position(|h| terms.iter().any(|term| h.contains(term)))
+        };
+
+        Some(Self {
+            ex_date: find(&["stacco"])?,
+            payment_date: find(&["pagamento"]),
+            amount: find(&["dividendo", "importo"])?,
+            dividend_type: find(&["tipo", "dettaglio"]),
+        })
+    }
+}
+
+impl Dividend {
+    pub fn same_payout(&self, other: &Dividend) -> bool {
+        self.isin == other.isin
+            && self.ex_date == other.ex_date
+            && self.dividend_type == other.dividend_type
+    }
+
+    pub(super) fn from_cells(
+        cells: &[ElementRef],
+        columns: &DividendColumns,
+        isin: &str,
+    ) -> ScraperResult<Self> {
+        let cell_text = |i: usize| {
+            cells
+                .get(i)
+                .map(|cell| cell.text().collect::<String>().trim().to_owned())
+        };
+
+        let ex_date = cell_text(columns.ex_date)
+            .and_then(|text| parse_date(&text).ok())
+            .ok_or(ScrapingError::ParsingErr)?;
+        // the payment date may not be announced yet
+        let payment_date = columns
+            .payment_date
+            .and_then(cell_text)
+            .and_then(|text| parse_date(&text).ok());
+        let amount = cell_text(columns.amount)
+            .and_then(|text| parse_number(&text).ok())
+            .ok_or(ScrapingError::ParsingErr)?
+            .value;
+        let dividend_type = columns
+            .dividend_type
+            .and_then(cell_text)
+            .map(|text| text.to_lowercase())
+            .filter(|text| !text.is_empty() && text != "-")
+            .unwrap_or_else(|| DEFAULT_DIVIDEND_TYPE.to_owned());
+
+        Ok(Self {
+            isin: isin.to_owned(),
+            ex_date,
+            payment_date,
+            amount,
+            dividend_type,
+            updated_at: Utc::now(),
+        })
+    }
+}
@@ -0,0 +1,204 @@
+// runs the parsers on the pages in tests/fixtures and compares the results with the
+// .json golden file next to each page, `UPDATE_GOLDEN=1 cargo test` rewrites them
+//
+// shares/<ISIN>_<description>.html are share pages (dati-completi.html),
+// listings/<description>.html are A-Z listing pages (listino-a-z.html)
+//
+// the fixtures are still hand-written reproductions of those pages, not pages
+// saved from the site, so they only cover the markup we know of; run
+// save_live_fixtures to replace them with the real pages listed in LIVE_PAGES
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{json, Value};
+use similar::TextDiff;
+
+use crate::{
+    isins::{self, types::ShareIsin},
+    shares::{self, outcomes::share_field_outcomes},
+};
+
+const UPDATE_ENV: &str = "UPDATE_GOLDEN";
+
+// where each fixture is saved from, `cargo test -p scraper save_live_fixtures --
+// --ignored` downloads them again; review the diff of the golden files before
+// accepting it with UPDATE_GOLDEN=1
+const LIVE_PAGES: &[(&str, &str)] = &[
+    (
+        "listings/a_page_1.html",
+        "https://www.borsaitaliana.it/borsa/azioni/listino-a-z.html?initial=A&page=1&lang=it",
+    ),
+    (
+        "listings/a_past_last_page.html",
+        "https://www.borsaitaliana.it/borsa/azioni/listino-a-z.html?initial=A&page=99&lang=it",
+    ),
+    (
+        "shares/IT0000062072_missing_fields.html",
+        "https://www.borsaitaliana.it/borsa/azioni/dati-completi.html?isin=IT0000062072&lang=it",
+    ),
+    (
+        "shares/IT0003128367_normal.html",
+        "https://www.borsaitaliana.it/borsa/azioni/dati-completi.html?isin=IT0003128367&lang=it",
+    ),
+    (
+        "shares/IT0003506190_suspended.html",
+        "https://www.borsaitaliana.it/borsa/azioni/dati-completi.html?isin=IT0003506190&lang=it",
+    ),
+];
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+fn fixtures(kind: &str) -> Vec<PathBuf> {
+    let dir = fixtures_dir().join(kind);
+    let mut pages: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("Can't read {}: {}", dir.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "html"))
+        .collect();
+    pages.sort();
+    pages
+}
+
+// timestamps of the scrape change on every run
+fn normalize(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if key == "updated_at" {
+                    *value = json!("<updated_at>");
+                } else {
+                    normalize(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(normalize),
+        _ => {}
+    }
+}
+
+fn fixture_isin(page: &Path) -> ShareIsin {
+    let stem = page.file_stem().unwrap().to_string_lossy();
+    let isin = stem.split('_').next().unwrap().to_owned();
+    ShareIsin::new(isin.clone(), isin).expect("fixture name must start with an ISIN")
+}
+
+fn share_output(page: &Path, res_txt: &str) -> Value {
+    let share_isin = fixture_isin(page);
+    let (share, failures) = shares::parse_document(res_txt, &share_isin);
+    let fields = share_field_outcomes(res_txt, &failures);
+
+    json!({ "share": share, "failures": failures, "fields": fields })
+}
+
+fn listing_output(res_txt: &str) -> Value {
+    let mut parsed = isins::parse_page(res_txt.to_owned());
+    let mut share_isins: Vec<ShareIsin> = parsed.unmetric().into_iter().collect();
+    share_isins.sort_by_key(|share_isin| share_isin.isin.to_string());
+
+    json!({ "share_isins": share_isins, "metrics": parsed.metrics })
+}
+
+// returns the diff against the golden file, or None if they match
+fn check_golden(page: &Path, mut output: Value) -> Option<String> {
+    normalize(&mut output);
+    let actual = serde_json::to_string_pretty(&output).unwrap() + "\n";
+    let golden_path = page.with_extension("json");
+
+    if std::env::var_os(UPDATE_ENV).is_some() {
+        fs::write(&golden_path, &actual).unwrap();
+        return None;
+    }
+
+    let expected = fs::read_to_string(&golden_path).unwrap_or_default();
+    if expected == actual {
+        return None;
+    }
+
+    let diff = TextDiff::from_lines(&expected, &actual)
+        .unified_diff()
+        .header(&golden_path.display().to_string(), "parsed")
+        .to_string();
+    Some(diff)
+}
+
+fn run_golden(kind: &str, output: impl Fn(&Path, &str) -> Value) {
+    let pages = fixtures(kind);
+    assert!(!pages.is_empty(), "No {} fixtures", kind);
+
+    let mismatches: Vec<String> = pages
+        .iter()
+        .filter_map(|page| {
+            let res_txt = fs::read_to_string(page).unwrap();
+            check_golden(page, output(page, &res_txt))
+        })
+        .collect();
+
+    assert!(
+        mismatches.is_empty(),
+        "{} {} fixtures differ from their golden file (rerun with {}=1 to accept):\n{}",
+        mismatches.len(),
+        kind,
+        UPDATE_ENV,
+        mismatches.join("\n")
+    );
+}
+
+#[test]
+fn share_pages_match_golden_files() {
+    run_golden("shares", share_output);
+}
+
+#[test]
+fn listing_pages_match_golden_files() {
+    run_golden("listings", |_, res_txt| listing_output(res_txt));
+}
+
+// the streaming extractor must give the same shares as the DOM
+#[cfg(feature = "streaming")]
+#[test]
+fn streamed_share_pages_match_dom() {
+    for page in fixtures("shares") {
+        let res_txt = fs::read_to_string(&page).unwrap();
+        let share_isin = fixture_isin(&page);
+
+        let (share, failures) = shares::parse_dom(&res_txt, &share_isin);
+        let mut dom = json!({ "share": share, "failures": failures });
+        normalize(&mut dom);
+
+        let (share, failures) = shares::parse_streamed(&res_txt, &share_isin).unwrap();
+        let mut streamed = json!({ "share": share, "failures": failures });
+        normalize(&mut streamed);
+
+        assert_eq!(dom, streamed, "{}", page.display());
+    }
+}
+
+#[test]
+fn fixtures_have_a_live_page() {
+    for page in fixtures("shares").into_iter().chain(fixtures("listings")) {
+        let fixture = page.strip_prefix(fixtures_dir()).unwrap();
+        assert!(
+            LIVE_PAGES
+                .iter()
+                .any(|(saved, _)| Path::new(saved) == fixture),
+            "{} isn't in LIVE_PAGES",
+            fixture.display()
+        );
+    }
+}
+
+#[tokio::test]
+#[ignore = "downloads the fixtures from borsaitaliana.it"]
+async fn save_live_fixtures() {
+    for (fixture, url) in LIVE_PAGES {
+        let res_txt = crate::get_page_text(url.to_string())
+            .await
+            .unwrap_or_else(|e| panic!("Can't download {}: {:?}", url, e));
+        fs::write(fixtures_dir().join(fixture), res_txt).unwrap();
+    }
+}
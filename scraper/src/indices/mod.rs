@@ -0,0 +1,229 @@
+use chrono::Utc;
+use futures::{stream::FuturesUnordered, StreamExt};
+use once_cell::sync::Lazy;
+use rust_decimal::Decimal;
+use scraper::{Html, Selector};
+use tracing::{debug, info, info_span, warn, Instrument};
+use types::{IndexComposition, IndexLevel, MarketIndex};
+
+use crate::{
+    errors::{ScraperResult, ScrapingError},
+    get_page_text,
+    metrics::{ScrapingMetrics, WithMetrics},
+    shares::{
+        mappings::field_mappings,
+        parsers::{ParseFailure, TryParse},
+        property_selector::PropertySelector,
+    },
+};
+
+pub mod types;
+
+// the constituent list is paginated, this bounds the All-Share one
+const MAX_CONSTITUENT_PAGES: u32 = 20;
+
+static TABLE_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("table").unwrap());
+static HEADER_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("th").unwrap());
+static SHARE_LINK_SELECTOR: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("td a[href*='isin=']").unwrap());
+
+pub async fn scrape_all_indices() -> WithMetrics<Vec<IndexComposition>> {
+    let mut metrics = ScrapingMetrics::empty();
+    metrics.total = MarketIndex::ALL.len() as i32;
+    let mut tasks = FuturesUnordered::new();
+
+    for index in MarketIndex::ALL {
+        tasks.push(
+            scrape_index(index).instrument(info_span!("scraping_index", code = index.code())),
+        );
+    }
+
+    let mut res: Vec<IndexComposition> = Vec::new();
+    while let Some(result) = tasks.next().await {
+        match result {
+            Ok((composition, failures)) => {
+                metrics.successful += 1;
+                metrics.errors.field_parsing_error += failures.len() as i32;
+                res.push(composition);
+            }
+            Err(e) => metrics.errors.update(e),
+        }
+    }
+    info!("Scraped a total of {} indices.", res.len());
+
+    WithMetrics::new(res, metrics)
+}
+
+pub async fn scrape_index(
+    index: MarketIndex,
+) -> ScraperResult<(IndexComposition, Vec<ParseFailure>)> {
+    let url = format!(
+        "https://www.borsaitaliana.it/borsa/indici/indici-in-continua/dettaglio.html?indexCode={}&lang=it",
+        index.code()
+    );
+    let res_txt = get_page_text(url)
+        .instrument(info_span!("fetching_page"))
+        .await?;
+    let (level, failures) = parse_index_page(&res_txt, index);
+
+    let mut constituents: Vec<String> = Vec::new();
+    let mut complete = false;
+    for page in 1..=MAX_CONSTITUENT_PAGES {
+        let url = format!(
+            "https://www.borsaitaliana.it/borsa/indici/indici-in-continua/componenti.html?indexCode={}&page={}&lang=it",
+            index.code(),
+            page
+        );
+        let res_txt = get_page_text(url)
+            .instrument(info_span!("fetching_constituents", page))
+            .await?;
+
+        // past the last page the list is empty or repeats the previous one
+        let found = parse_constituents_page(&res_txt);
+        let new: Vec<String> = found
+            .into_iter()
+            .filter(|isin| !constituents.contains(isin))
+            .collect();
+        if new.is_empty() {
+            complete = true;
+            break;
+        }
+        constituents.extend(new);
+    }
+    if !complete {
+        warn!(
+            "Constituents of {} go past {} pages, the list is incomplete",
+            index.name(),
+            MAX_CONSTITUENT_PAGES
+        );
+    }
+
+    // an index always has constituents, without them the list page changed
+    if constituents.is_empty() {
+        warn!("No constituents found for {}", index.name());
+        return Err(ScrapingError::InvalidPage);
+    }
+    debug!(
+        "Found {} constituents for {}",
+        constituents.len(),
+        index.name()
+    );
+
+    Ok((
+        IndexComposition {
+            level,
+            constituents,
+            complete,
+        },
+        failures,
+    ))
+}
+
+pub fn parse_index_page(res_txt: &str, index: MarketIndex) -> (IndexLevel, Vec<ParseFailure>) {
+    debug!("Parsing index page");
+
+    let doc = Html::parse_document(res_txt);
+    let selector = PropertySelector::with_mappings(&doc, &field_mappings().index);
+    let mut failures = Vec::new();
+
+    let mut decimal = |prop: &str| -> Option<Decimal> {
+        match selector.get_property(prop)?.try_parse(prop) {
+            Ok(value) => Some(value),
+            Err(failure) if failure.is_no_value() => None,
+            Err(failure) => {
+                warn!("{}", failure);
+                failures.push(failure);
+                None
+            }
+        }
+    };
+
+    let level = IndexLevel {
+        code: index.code().to_owned(),
+        name: index.name().to_owned(),
+        valore: decimal("valore"),
+        var_percentuale: decimal("var_percentuale"),
+        var_assoluta: decimal("var_assoluta"),
+        updated_at: Utc::now(),
+    };
+
+    (level, failures)
+}
+
+// constituents link to their share page from the table with the name and price
+// columns, e.g. "scheda/IT0003128367.html?isin=IT0003128367"; links of other
+// tables and widgets of the page are ignored
+pub fn parse_constituents_page(res_txt: &str) -> Vec<String> {
+    let doc = Html::parse_document(res_txt);
+    let Some(table) = doc.select(&TABLE_SELECTOR).find(|table| {
+        let headers: Vec<String> = table
+            .select(&HEADER_SELECTOR)
+            .map(|th| th.text().collect::<String>().trim().to_lowercase())
+            .collect();
+        headers.iter().any(|h| h.contains("nome")) && headers.iter().any(|h| h.contains("ultimo"))
+    }) else {
+        debug!("No constituents table found");
+        return Vec::new();
+    };
+
+    let mut isins: Vec<String> = Vec::new();
+    for link in table.select(&SHARE_LINK_SELECTOR) {
+        let Some(href) = link.value().attr("href") else {
+            continue;
+        };
+        let Some(isin) = href
+            .split_once("isin=")
+            .and_then(|(_, rest)| rest.get(..12))
+            .filter(|isin| isin.chars().all(|c| c.is_ascii_alphanumeric()))
+        else {
+            continue;
+        };
+
+        let isin = isin.to_uppercase();
+        if !isins.contains(&isin) {
+            isins.push(isin);
+        }
+    }
+
+    isins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_only_the_constituents_table() {
+        let page = r#"<html><body>
+            <div class="ticker"><a href="/scheda.html?isin=IT0000072618">Intesa</a></div>
+            <table><tr><th>Più scambiati</th><th>Var %</th></tr>
+              <tr><td><a href="/scheda.html?isin=IT0005239360">Unicredit</a></td><td>+1,2</td></tr>
+            </table>
+            <table>
+              <tr><th>Nome</th><th>Ultimo</th><th>Var %</th></tr>
+              <tr><td><a href="/scheda/IT0003128367.html?isin=IT0003128367">Enel</a></td>
+                  <td>6,868</td><td>+0,41</td></tr>
+              <tr><td><a href="/scheda.html?isin=it0000062072&lang=it">Generali</a></td>
+                  <td>27,30</td><td>-0,12</td></tr>
+              <tr><td><a href="/scheda.html?isin=IT0003128367">Enel</a></td>
+                  <td>6,868</td><td>+0,41</td></tr>
+            </table>
+            <aside><a href="/scheda.html?isin=IT0005239360">Unicredit</a></aside>
+        </body></html>"#;
+
+        assert_eq!(
+            parse_constituents_page(page),
+            ["IT0003128367", "IT0000062072"]
+        );
+    }
+
+    #[test]
+    fn finds_no_constituents_without_the_table() {
+        let page = r#"<html><body>
+            <div class="ticker"><a href="/scheda.html?isin=IT0000072618">Intesa</a></div>
+            <table><tr><td><a href="/scheda.html?isin=IT0005239360">Unicredit</a></td></tr></table>
+        </body></html>"#;
+
+        assert!(parse_constituents_page(page).is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::shares::mappings::{DefaultMapping, MatchMode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MarketIndex {
+    FtseMib,
+    AllShare,
+    Star,
+}
+
+impl MarketIndex {
+    pub const ALL: [MarketIndex; 3] = [
+        MarketIndex::FtseMib,
+        MarketIndex::AllShare,
+        MarketIndex::Star,
+    ];
+
+    // the code used by borsaitaliana.it in index urls
+    pub fn code(&self) -> &'static str {
+        match self {
+            MarketIndex::FtseMib => "FTSEMIB",
+            MarketIndex::AllShare => "ITLMS",
+            MarketIndex::Star => "ITSTAR",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MarketIndex::FtseMib => "FTSE MIB",
+            MarketIndex::AllShare => "FTSE Italia All-Share",
+            MarketIndex::Star => "FTSE Italia STAR",
+        }
+    }
+}
+
+// the fields of `IndexLevel` read from the page through the mappings
+pub(crate) fn index_default_mappings() -> Vec<DefaultMapping> {
+    let mapping = |field, terms| DefaultMapping {
+        field,
+        terms,
+        match_mode: MatchMode::Contains,
+        value_selector: None,
+    };
+
+    vec![
+        mapping("valore", &["ultimo valore", "valore ultimo"]),
+        mapping("var_percentuale", &["var %"]),
+        mapping("var_assoluta", &["var assoluta"]),
+    ]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct IndexLevel {
+    pub code: String,
+    pub name: String,
+    pub valore: Option<Decimal>,
+    pub var_percentuale: Option<Decimal>,
+    pub var_assoluta: Option<Decimal>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexComposition {
+    pub level: IndexLevel,
+    // ISINs of the current constituents
+    pub constituents: Vec<String>,
+    // false when the list was cut at the page limit, the shares missing from it
+    // may still be constituents
+    pub complete: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct IndexMembership {
+    pub index_code: String,
+    pub isin: String,
+    pub valid_from: DateTime<Utc>,
+    // None while the share is still a constituent
+    pub valid_to: Option<DateTime<Utc>>,
+}
@@ -70,7 +70,7 @@ async fn scrape_isins_at_page(letter: char, page: u8) -> WithMetrics<HashSet<Sha
     WithMetrics::new(res, metrics)
 }
 
-fn parse_page(res_txt: String) -> WithMetrics<HashSet<ShareIsin>> {
+pub fn parse_page(res_txt: String) -> WithMetrics<HashSet<ShareIsin>> {
     debug!("Parsing ISIN page");
 
     let doc = Html::parse_document(&res_txt);
@@ -1,11 +1,11 @@
 use std::fmt::{Display, Formatter};
 use std::hash::Hash;
 
-use crate::shares::parsers::SafeParse;
-use chrono::NaiveDateTime;
+use crate::shares::parsers::TryParse;
+use chrono::{DateTime, Utc};
 use scraper::ElementRef;
 use serde::{Deserialize, Serialize};
-use tracing::debug;
+use tracing::{debug, warn};
 
 use crate::errors::{ScraperResult, ScrapingError};
 
@@ -62,7 +62,7 @@ pub struct ShareIsin {
     pub share_name: String,
     #[sqlx(try_from = "String")]
     pub isin: Isin,
-    pub updated_at: NaiveDateTime,
+    pub updated_at: DateTime<Utc>,
 }
 
 // don't include "updated_at" for HashSet
@@ -84,7 +84,7 @@ impl ShareIsin {
             Isin::new(isin_str).map(|isin| ShareIsin {
                 share_name: name.to_string(),
                 isin,
-                updated_at: chrono::offset::Utc::now().naive_utc(),
+                updated_at: Utc::now(),
             })
         } else {
             None
@@ -111,8 +111,12 @@ impl ShareIsin {
         let name: String = isin_element
             .select(&isin_share_name_selector)
             .next()
-            .and_then(|el| el.safe_parse())
-            .ok_or(ScrapingError::InvalidPage)?;
+            .ok_or(ScrapingError::InvalidPage)?
+            .try_parse("share_name")
+            .map_err(|failure| {
+                warn!("{}", failure);
+                ScrapingError::ParsingErr
+            })?;
         debug!("Name is {}", name);
 
         Self::new(name, isin_str.to_owned()).ok_or(ScrapingError::ParsingErr)
@@ -1,8 +1,16 @@
+pub mod dividends;
 mod errors;
 pub mod exponential_backoff;
+// integration tests can't import this crate, its name clashes with the `scraper` dependency
+#[cfg(test)]
+mod golden_tests;
+pub mod indices;
 pub mod isins;
 pub mod metrics;
+pub mod press_releases;
+pub mod quarantine;
 pub mod shares;
+pub mod trades;
 
 use std::time::Duration;
 
@@ -14,6 +22,14 @@ use tracing::{debug, debug_span, error, Instrument};
 
 use crate::exponential_backoff::{exponential_backoff, BackoffMessage};
 
+// used by the code of #[derive(Scrapable)], so that the crates using it don't
+// need these dependencies
+#[doc(hidden)]
+pub mod derive_support {
+    pub use chrono::Utc;
+    pub use tracing::{debug, warn};
+}
+
 static CLIENT: Lazy<Client> = Lazy::new(|| {
     reqwest::Client::builder()
         .pool_max_idle_per_host(100) // Keep more connections alive
@@ -57,6 +57,8 @@ pub struct ScrapingErrorMetrics {
     pub timeout: i32,
     pub max_retries: i32,
     pub parsing_error: i32,
+    // fields that couldn't be parsed on otherwise valid pages
+    pub field_parsing_error: i32,
 }
 
 impl Add for ScrapingErrorMetrics {
@@ -69,6 +71,7 @@ impl Add for ScrapingErrorMetrics {
             timeout: self.timeout + rhs.timeout,
             max_retries: self.max_retries + rhs.max_retries,
             parsing_error: self.parsing_error + rhs.parsing_error,
+            field_parsing_error: self.field_parsing_error + rhs.field_parsing_error,
         }
     }
 }
@@ -81,6 +84,7 @@ impl ScrapingErrorMetrics {
             timeout: 0,
             max_retries: 0,
             parsing_error: 0,
+            field_parsing_error: 0,
         }
     }
 
@@ -0,0 +1,187 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+use futures::{stream::FuturesUnordered, StreamExt};
+use once_cell::sync::Lazy;
+use scraper::{ElementRef, Html, Selector};
+use tracing::{debug, info, info_span, warn, Instrument};
+use types::{parse_published_at, PressRelease};
+
+use crate::{
+    errors::{ScraperResult, ScrapingError},
+    get_page_text,
+    isins::types::ShareIsin,
+    metrics::{ScrapingMetrics, WithMetrics},
+    shares::parsers::collapse_whitespace,
+};
+
+pub mod types;
+
+const BASE_URL: &str = "https://www.borsaitaliana.it";
+
+static ROW_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("tr").unwrap());
+static CELL_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("td").unwrap());
+static LINK_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("a[href]").unwrap());
+// holds the text of a release page, without the navigation and footer of the site
+static BODY_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("article").unwrap());
+
+// `stored_links` are the links of each isin whose body is already stored, their
+// pages aren't fetched again
+pub async fn scrape_all_press_releases(
+    share_isins: Vec<ShareIsin>,
+    stored_links: &HashMap<String, HashSet<String>>,
+) -> WithMetrics<Vec<PressRelease>> {
+    let mut metrics = ScrapingMetrics::empty();
+    metrics.total = share_isins.len() as i32;
+    let no_links = HashSet::new();
+    let mut tasks = FuturesUnordered::new();
+
+    for share_isin in share_isins.iter() {
+        let isin_str = share_isin.isin.to_string();
+        let stored = stored_links.get(&isin_str).unwrap_or(&no_links);
+        tasks.push(
+            scrape_press_releases(share_isin, stored)
+                .instrument(info_span!("scraping_press_releases", isin = isin_str)),
+        );
+    }
+
+    let mut res: Vec<PressRelease> = Vec::new();
+    while let Some(result) = tasks.next().await {
+        match result {
+            Ok(releases) => {
+                metrics.successful += 1;
+                res.extend(releases);
+            }
+            Err(e) => metrics.errors.update(e),
+        }
+    }
+    info!("Scraped a total of {} press releases.", res.len());
+
+    WithMetrics::new(res, metrics)
+}
+
+pub async fn scrape_press_releases(
+    share_isin: &ShareIsin,
+    stored_links: &HashSet<String>,
+) -> ScraperResult<Vec<PressRelease>> {
+    let url = format!(
+        "{}/borsa/notizie/comunicati-stampa/lista.html?isin={}&lang=it",
+        BASE_URL, share_isin.isin
+    );
+
+    let res_txt = get_page_text(url)
+        .instrument(info_span!("fetching_page"))
+        .await?;
+
+    let mut releases = parse_list_page(&res_txt, &share_isin.isin.to_string())?;
+
+    // a missing body doesn't invalidate the release, it's filled on the next refresh;
+    // the releases already stored keep their body
+    let new_releases = releases
+        .iter_mut()
+        .filter(|release| !stored_links.contains(&release.link));
+    for release in new_releases {
+        match get_page_text(release.link.clone())
+            .instrument(info_span!("fetching_release", link = release.link))
+            .await
+        {
+            Ok(page) => release.body = parse_release_page(&page),
+            Err(e) => warn!("Unable to fetch press release {}: {:?}", release.link, e),
+        }
+    }
+
+    Ok(releases)
+}
+
+// every row with a link and a publication date is a release
+pub fn parse_list_page(res_txt: &str, isin: &str) -> ScraperResult<Vec<PressRelease>> {
+    debug!("Parsing press releases page");
+
+    let doc = Html::parse_document(res_txt);
+    let mut releases: Vec<PressRelease> = Vec::new();
+
+    for row in doc.select(&ROW_SELECTOR) {
+        let Some(link) = row.select(&LINK_SELECTOR).next() else {
+            continue;
+        };
+        let Some(published_at) = row
+            .select(&CELL_SELECTOR)
+            .find_map(|cell| parse_published_at(&element_text(&cell)))
+        else {
+            continue;
+        };
+
+        let href = link.value().attr("href").unwrap_or_default();
+        let link_url = if href.starts_with("http") {
+            href.to_owned()
+        } else {
+            format!("{}{}", BASE_URL, href)
+        };
+        if releases.iter().any(|r| r.link == link_url) {
+            continue;
+        }
+
+        releases.push(PressRelease {
+            isin: isin.to_owned(),
+            link: link_url,
+            title: element_text(&link),
+            published_at,
+            body: None,
+            updated_at: Utc::now(),
+        });
+    }
+
+    if releases.is_empty() && doc.select(&ROW_SELECTOR).next().is_none() {
+        return Err(ScrapingError::InvalidPage);
+    }
+    debug!("Found {} press releases", releases.len());
+
+    Ok(releases)
+}
+
+// None when the page has no release text
+pub fn parse_release_page(res_txt: &str) -> Option<String> {
+    let doc = Html::parse_document(res_txt);
+
+    doc.select(&BODY_SELECTOR)
+        .next()
+        .map(|element| element_text(&element))
+        .filter(|text| !text.is_empty())
+}
+
+// joins the text nodes collapsing whitespace
+fn element_text(element: &ElementRef) -> String {
+    collapse_whitespace(element.text())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_text_of_the_release() {
+        let page = r#"<html><body>
+            <nav><a href="/">Borsa Italiana</a></nav>
+            <article><h1>Enel: risultati 2024</h1>
+              <p>Ricavi in crescita   del 5%.</p><p>Dividendo confermato.</p></article>
+            <footer>Copyright</footer>
+        </body></html>"#;
+
+        assert_eq!(
+            parse_release_page(page).as_deref(),
+            Some("Enel: risultati 2024 Ricavi in crescita del 5%. Dividendo confermato.")
+        );
+    }
+
+    #[test]
+    fn leaves_the_body_empty_without_release_text() {
+        let pages = [
+            "<html><body><nav>Menu</nav><main><p>Pagina non trovata</p></main></body></html>",
+            "<html><body><article> </article><footer>Copyright</footer></body></html>",
+        ];
+
+        for page in pages {
+            assert_eq!(parse_release_page(page), None, "{}", page);
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::shares::parsers::rome_to_utc;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PressRelease {
+    pub isin: String,
+    pub link: String,
+    pub title: String,
+    pub published_at: DateTime<Utc>,
+    // None when the release page couldn't be fetched
+    pub body: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+pub(super) fn parse_published_at(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+
+    // 29/11/2024 - 17:35
+    for fmt in [
+        "%d/%m/%Y - %H:%M",
+        "%d/%m/%Y %H:%M",
+        "%d/%m/%y - %H.%M.%S",
+        "%d/%m/%y %H.%M.%S",
+    ] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(text, fmt) {
+            return rome_to_utc(datetime);
+        }
+    }
+
+    // some listings only show the day
+    ["%d/%m/%Y", "%d/%m/%y"]
+        .into_iter()
+        .find_map(|fmt| NaiveDate::parse_from_str(text, fmt).ok())
+        .and_then(|date| rome_to_utc(date.and_time(NaiveTime::MIN)))
+}
@@ -0,0 +1,55 @@
+// pages that made a parser panic are saved here to be reproduced later, e.g. with
+// `share_service parse --isin <ISIN> quarantine/<file>.html`
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::Utc;
+use once_cell::sync::OnceCell;
+use serde_json::json;
+use tracing::{error, warn};
+
+const DEFAULT_QUARANTINE_DIR: &str = "quarantine";
+
+static QUARANTINE_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+// only the first call has effect
+pub fn init_quarantine(dir: PathBuf) {
+    if QUARANTINE_DIR.set(dir).is_err() {
+        warn!("Quarantine directory was already initialized");
+    }
+}
+
+pub fn quarantine_dir() -> &'static Path {
+    QUARANTINE_DIR.get_or_init(|| PathBuf::from(DEFAULT_QUARANTINE_DIR))
+}
+
+// writes <ISIN>_<timestamp>.html with the page and a .json next to it with the error
+pub fn quarantine_page(isin: &str, res_txt: &str, error: &str) -> Option<PathBuf> {
+    let dir = quarantine_dir();
+    let now = Utc::now();
+    let page_path = dir.join(format!("{}_{}.html", isin, now.format("%Y%m%dT%H%M%S%.3f")));
+
+    let res = fs::create_dir_all(dir)
+        .and_then(|_| fs::write(&page_path, res_txt))
+        .and_then(|_| {
+            let details = json!({ "isin": isin, "error": error, "quarantined_at": now });
+            fs::write(
+                page_path.with_extension("json"),
+                serde_json::to_string_pretty(&details).unwrap(),
+            )
+        });
+
+    match res {
+        Ok(_) => {
+            warn!("Page of {} quarantined to {}", isin, page_path.display());
+            Some(page_path)
+        }
+        Err(e) => {
+            error!("Unable to quarantine page of {}: {}", isin, e);
+            None
+        }
+    }
+}
@@ -0,0 +1,445 @@
+use std::{collections::HashSet, fmt::Display, path::Path};
+
+use once_cell::sync::OnceCell;
+use scraper::Selector;
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use super::models::{company_profile::CompanyProfile, share::Share, ScrapableStruct};
+use crate::indices::types::index_default_mappings;
+
+static FIELD_MAPPINGS: OnceCell<FieldMappings> = OnceCell::new();
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMode {
+    Exact,
+    #[default]
+    Contains,
+}
+
+// a compiled-in mapping, declared with #[scrape(...)] on `ScrapableStruct`s
+#[derive(Debug, Clone)]
+pub struct DefaultMapping {
+    pub field: &'static str,
+    pub terms: &'static [&'static str],
+    pub match_mode: MatchMode,
+    pub value_selector: Option<&'static str>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FieldMappingConfig {
+    field: String,
+    terms: Vec<String>,
+    #[serde(default)]
+    match_mode: MatchMode,
+    #[serde(default)]
+    value_selector: Option<String>,
+}
+
+impl From<DefaultMapping> for FieldMappingConfig {
+    fn from(mapping: DefaultMapping) -> Self {
+        Self {
+            field: mapping.field.to_owned(),
+            terms: mapping.terms.iter().map(|term| term.to_string()).collect(),
+            match_mode: mapping.match_mode,
+            value_selector: mapping.value_selector.map(str::to_owned),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MappingsConfig {
+    #[serde(default)]
+    share: Vec<FieldMappingConfig>,
+    #[serde(default)]
+    profile: Vec<FieldMappingConfig>,
+    #[serde(default)]
+    index: Vec<FieldMappingConfig>,
+}
+
+#[derive(Debug)]
+pub struct FieldMapping {
+    pub field: String,
+    pub terms: Vec<String>,
+    pub match_mode: MatchMode,
+    // None uses the default value selector
+    pub value_selector: Option<ValueSelector>,
+}
+
+#[derive(Debug)]
+pub struct ValueSelector {
+    // kept for extractors that compile their own selectors
+    pub css: String,
+    pub selector: Selector,
+}
+
+pub type Mappings = Vec<FieldMapping>;
+
+#[derive(Debug)]
+pub struct FieldMappings {
+    pub share: Mappings,
+    pub profile: Mappings,
+    pub index: Mappings,
+}
+
+#[derive(Debug)]
+pub enum MappingError {
+    Io(std::io::Error),
+    Toml(toml::de::Error),
+    InvalidSelector { page: &'static str, field: String },
+    EmptyTerms { page: &'static str, field: String },
+    DuplicateField { page: &'static str, field: String },
+    UnknownField { page: &'static str, field: String },
+    MissingField { page: &'static str, field: String },
+}
+
+impl Display for MappingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MappingError::Io(e) => write!(f, "unable to read mappings: {}", e),
+            MappingError::Toml(e) => write!(f, "invalid mappings file: {}", e),
+            MappingError::InvalidSelector { page, field } => {
+                write!(f, "invalid value selector for {}.{}", page, field)
+            }
+            MappingError::EmptyTerms { page, field } => {
+                write!(f, "no search terms for {}.{}", page, field)
+            }
+            MappingError::DuplicateField { page, field } => {
+                write!(f, "{}.{} is mapped more than once", page, field)
+            }
+            MappingError::UnknownField { page, field } => {
+                write!(f, "{}.{} isn't a scraped field", page, field)
+            }
+            MappingError::MissingField { page, field } => {
+                write!(f, "{}.{} has no mapping", page, field)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MappingError {}
+
+impl FieldMapping {
+    // `label` must be lowercase
+    pub fn matches(&self, label: &str) -> bool {
+        match self.match_mode {
+            MatchMode::Exact => self.terms.iter().any(|term| label.trim() == term),
+            MatchMode::Contains => self.terms.iter().any(|term| label.contains(term.as_str())),
+        }
+    }
+
+    fn from_config(page: &'static str, config: FieldMappingConfig) -> Result<Self, MappingError> {
+        if config.terms.is_empty() {
+            return Err(MappingError::EmptyTerms {
+                page,
+                field: config.field,
+            });
+        }
+
+        let value_selector = match config.value_selector {
+            Some(css) => match Selector::parse(&css).ok() {
+                Some(selector) => Some(ValueSelector { css, selector }),
+                None => {
+                    return Err(MappingError::InvalidSelector {
+                        page,
+                        field: config.field,
+                    })
+                }
+            },
+            None => None,
+        };
+
+        Ok(Self {
+            terms: config
+                .terms
+                .iter()
+                .map(|term| term.to_lowercase())
+                .collect(),
+            field: config.field,
+            match_mode: config.match_mode,
+            value_selector,
+        })
+    }
+}
+
+// the mappings in use, the compiled-in defaults unless `init_mappings` was called
+pub fn field_mappings() -> &'static FieldMappings {
+    FIELD_MAPPINGS.get_or_init(|| load_mappings(None).expect("Invalid default field mappings"))
+}
+
+// loads the mappings, overriding the defaults with the ones in `path`, and
+// validates them against the scraped fields; only the first call has effect
+pub fn init_mappings(path: Option<&Path>) -> Result<&'static FieldMappings, MappingError> {
+    let mappings = load_mappings(path)?;
+    if FIELD_MAPPINGS.set(mappings).is_err() {
+        warn!("Field mappings were already initialized");
+    }
+
+    Ok(field_mappings())
+}
+
+pub fn load_mappings(path: Option<&Path>) -> Result<FieldMappings, MappingError> {
+    let overrides = match path {
+        Some(path) => {
+            info!("Loading field mappings from {}", path.display());
+            let text = std::fs::read_to_string(path).map_err(MappingError::Io)?;
+            Some(parse_config(&text)?)
+        }
+        None => None,
+    };
+
+    build_mappings(overrides)
+}
+
+fn build_mappings(overrides: Option<MappingsConfig>) -> Result<FieldMappings, MappingError> {
+    let share_defaults = Share::default_mappings();
+    let profile_defaults = CompanyProfile::default_mappings();
+    let index_defaults = index_default_mappings();
+    let expected =
+        |defaults: &[DefaultMapping]| defaults.iter().map(|m| m.field).collect::<Vec<_>>();
+    let (share_fields, profile_fields, index_fields) = (
+        expected(&share_defaults),
+        expected(&profile_defaults),
+        expected(&index_defaults),
+    );
+
+    let mut config = MappingsConfig {
+        share: share_defaults.into_iter().map(Into::into).collect(),
+        profile: profile_defaults.into_iter().map(Into::into).collect(),
+        index: index_defaults.into_iter().map(Into::into).collect(),
+    };
+
+    if let Some(overrides) = overrides {
+        override_page("share", &mut config.share, overrides.share)?;
+        override_page("profile", &mut config.profile, overrides.profile)?;
+        override_page("index", &mut config.index, overrides.index)?;
+    }
+
+    Ok(FieldMappings {
+        share: build_page("share", config.share, &share_fields)?,
+        profile: build_page("profile", config.profile, &profile_fields)?,
+        index: build_page("index", config.index, &index_fields)?,
+    })
+}
+
+fn parse_config(text: &str) -> Result<MappingsConfig, MappingError> {
+    toml::from_str(text).map_err(MappingError::Toml)
+}
+
+// a field listed twice in the file would silently replace its first entry
+fn override_page(
+    page: &'static str,
+    defaults: &mut Vec<FieldMappingConfig>,
+    overrides: Vec<FieldMappingConfig>,
+) -> Result<(), MappingError> {
+    let mut overridden = HashSet::new();
+
+    for mapping in overrides {
+        if !overridden.insert(mapping.field.clone()) {
+            return Err(MappingError::DuplicateField {
+                page,
+                field: mapping.field,
+            });
+        }
+        match defaults.iter_mut().find(|m| m.field == mapping.field) {
+            Some(default) => *default = mapping,
+            None => defaults.push(mapping),
+        }
+    }
+
+    Ok(())
+}
+
+fn build_page(
+    page: &'static str,
+    configs: Vec<FieldMappingConfig>,
+    expected: &[&str],
+) -> Result<Mappings, MappingError> {
+    let mut mappings: Mappings = Vec::with_capacity(configs.len());
+
+    for config in configs {
+        if !expected.contains(&config.field.as_str()) {
+            return Err(MappingError::UnknownField {
+                page,
+                field: config.field,
+            });
+        }
+        if mappings.iter().any(|m| m.field == config.field) {
+            return Err(MappingError::DuplicateField {
+                page,
+                field: config.field,
+            });
+        }
+        mappings.push(FieldMapping::from_config(page, config)?);
+    }
+
+    if let Some(missing) = expected
+        .iter()
+        .find(|field| !mappings.iter().any(|m| m.field == **field))
+    {
+        return Err(MappingError::MissingField {
+            page,
+            field: missing.to_string(),
+        });
+    }
+
+    Ok(mappings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_str(text: &str) -> Result<FieldMappings, MappingError> {
+        build_mappings(Some(parse_config(text)?))
+    }
+
+    fn mapping<'a>(mappings: &'a Mappings, field: &str) -> &'a FieldMapping {
+        mappings.iter().find(|m| m.field == field).unwrap()
+    }
+
+    #[test]
+    fn loads_the_defaults() {
+        let mappings = load_mappings(None).unwrap();
+
+        assert_eq!(mappings.share.len(), Share::fields().len());
+        assert_eq!(mappings.profile.len(), CompanyProfile::fields().len());
+        let prezzo = mapping(&mappings.share, "prezzo_ultimo_contratto");
+        assert_eq!(prezzo.terms, ["prezzo ultimo contratto"]);
+        assert_eq!(prezzo.match_mode, MatchMode::Contains);
+        assert!(prezzo.value_selector.is_none());
+    }
+
+    #[test]
+    fn loads_the_example_file() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("mappings.example.toml");
+        let mappings = load_mappings(Some(&path)).unwrap();
+
+        let prezzo = mapping(&mappings.share, "prezzo_ultimo_contratto");
+        assert_eq!(prezzo.terms, ["prezzo ultimo contratto", "ultimo prezzo"]);
+        assert_eq!(
+            mapping(&mappings.profile, "sito_web").match_mode,
+            MatchMode::Exact
+        );
+        let valore = mapping(&mappings.index, "valore");
+        assert_eq!(valore.value_selector.as_ref().unwrap().css, "span.t-text");
+        // the fields not in the file keep their default
+        assert_eq!(mapping(&mappings.share, "max_oggi").terms, ["max oggi"]);
+    }
+
+    #[test]
+    fn lowercases_the_terms() {
+        let mappings = load_str(
+            r#"
+            [[share]]
+            field = "max_oggi"
+            terms = ["Max Oggi"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(mapping(&mappings.share, "max_oggi").terms, ["max oggi"]);
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let err = load_str(
+            r#"
+            [[share]]
+            field = "prezzo_di_chiusura"
+            terms = ["chiusura"]
+            "#,
+        )
+        .unwrap_err();
+
+        assert!(
+            matches!(&err, MappingError::UnknownField { page: "share", field } if field == "prezzo_di_chiusura"),
+            "{:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn rejects_fields_listed_twice() {
+        let err = load_str(
+            r#"
+            [[profile]]
+            field = "sito_web"
+            terms = ["sito web"]
+
+            [[profile]]
+            field = "sito_web"
+            terms = ["website"]
+            "#,
+        )
+        .unwrap_err();
+
+        assert!(
+            matches!(&err, MappingError::DuplicateField { page: "profile", field } if field == "sito_web"),
+            "{:?}",
+            err
+        );
+    }
+
+    // a file can't remove a default, only the compiled-in mappings can miss one
+    #[test]
+    fn rejects_missing_fields() {
+        let configs = Share::default_mappings()
+            .into_iter()
+            .filter(|m| m.field != "max_oggi")
+            .map(Into::into)
+            .collect();
+
+        let err = build_page("share", configs, &Share::fields()).unwrap_err();
+
+        assert!(
+            matches!(&err, MappingError::MissingField { page: "share", field } if field == "max_oggi"),
+            "{:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_entries() {
+        let err = load_str(
+            r#"
+            [[share]]
+            field = "max_oggi"
+            terms = []
+            "#,
+        )
+        .unwrap_err();
+        assert!(matches!(err, MappingError::EmptyTerms { .. }), "{:?}", err);
+
+        let err = load_str(
+            r#"
+            [[share]]
+            field = "max_oggi"
+            terms = ["max oggi"]
+            value_selector = "span[["
+            "#,
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, MappingError::InvalidSelector { .. }),
+            "{:?}",
+            err
+        );
+
+        let err = load_str(
+            r#"
+            [[share]]
+            field = "max_oggi"
+            labels = ["max oggi"]
+            "#,
+        )
+        .unwrap_err();
+        assert!(matches!(err, MappingError::Toml(_)), "{:?}", err);
+
+        let err = load_mappings(Some(Path::new("does/not/exist.toml"))).unwrap_err();
+        assert!(matches!(err, MappingError::Io(_)), "{:?}", err);
+    }
+}
@@ -1,12 +1,30 @@
+pub mod mappings;
 mod models;
+pub mod numbers;
+pub mod outcomes;
 pub mod parsers;
-mod property_selector;
-pub use models::{share::Share, ScrapableStruct};
+pub mod profiles;
+pub mod property_selector;
+#[cfg(feature = "streaming")]
+pub mod streaming;
+pub use models::{
+    company_profile::CompanyProfile,
+    market_phase::MarketPhase,
+    market_segment::MarketSegment,
+    order_book::{BookSide, OrderBook, OrderBookLevel},
+    price_data::PriceData,
+    share::Share,
+    ScrapableStruct,
+};
 
 use futures::future::join_all;
 use once_cell::sync::Lazy;
 use scraper::Html;
-use std::time::Duration;
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    time::Duration,
+};
 use tokio::{task, time::timeout};
 use tracing::{error, info, info_span, warn, Instrument};
 
@@ -15,7 +33,9 @@ use crate::{
     get_page_text,
     isins::types::ShareIsin,
     metrics::{ScrapingMetrics, WithMetrics},
+    quarantine::quarantine_page,
 };
+use parsers::ParseFailure;
 use property_selector::PropertySelector;
 
 static PARSE_POOL: Lazy<rayon::ThreadPool> = Lazy::new(|| {
@@ -51,8 +71,9 @@ pub async fn scrape_all_shares(share_isins: Vec<ShareIsin>) -> WithMetrics<Vec<S
     let mut res: Vec<Share> = Vec::new();
     for result in results {
         match result {
-            Ok(Ok(result)) => {
+            Ok(Ok((result, failures))) => {
                 metrics.successful += 1;
+                metrics.errors.field_parsing_error += failures.len() as i32;
                 res.push(result);
             }
             Ok(Err(e)) => metrics.errors.update(e),
@@ -91,7 +112,7 @@ pub async fn scrape_all_shares(share_isins: Vec<ShareIsin>) -> WithMetrics<Vec<S
 pub async fn scrape_share_with_max_duration(
     share_isin: ShareIsin,
     max_duration: u64,
-) -> ScraperResult<Share> {
+) -> ScraperResult<(Share, Vec<ParseFailure>)> {
     match timeout(Duration::from_secs(max_duration), scrape_share(&share_isin)).await {
         Ok(res) => {
             if let Err(e) = &res {
@@ -109,7 +130,7 @@ pub async fn scrape_share_with_max_duration(
     }
 }
 
-pub async fn scrape_share(share_isin: &ShareIsin) -> ScraperResult<Share> {
+pub async fn scrape_share(share_isin: &ShareIsin) -> ScraperResult<(Share, Vec<ParseFailure>)> {
     let isin = &share_isin.isin;
     let url = format!(
         "https://www.borsaitaliana.it/borsa/azioni/dati-completi.html?isin={}&lang=it",
@@ -120,22 +141,94 @@ pub async fn scrape_share(share_isin: &ShareIsin) -> ScraperResult<Share> {
         .instrument(info_span!("fetching_page"))
         .await?;
 
-    let share = parse_page(res_txt, share_isin).await;
-    Ok(share)
+    parse_page(res_txt, share_isin).await
 }
 
-async fn parse_page(res_txt: String, share_isin: &ShareIsin) -> Share {
+// a panicking parser would abort the process from the rayon pool, the panic is
+// caught and the page quarantined instead
+pub async fn parse_page(
+    res_txt: String,
+    share_isin: &ShareIsin,
+) -> ScraperResult<(Share, Vec<ParseFailure>)> {
+    parse_page_with(res_txt, share_isin, parse_document).await
+}
+
+type ParseFn = fn(&str, &ShareIsin) -> (Share, Vec<ParseFailure>);
+
+async fn parse_page_with(
+    res_txt: String,
+    share_isin: &ShareIsin,
+    parse: ParseFn,
+) -> ScraperResult<(Share, Vec<ParseFailure>)> {
     let share_isin = share_isin.clone();
     let (sender, receiver) = tokio::sync::oneshot::channel();
 
     PARSE_POOL.spawn(move || {
-        let doc = Html::parse_document(&res_txt);
-        let selector = PropertySelector::new(&doc);
-        let share = Share::from_selector(&share_isin, &selector);
-        let _ = sender.send(share);
+        let res = panic::catch_unwind(AssertUnwindSafe(|| parse(&res_txt, &share_isin))).map_err(
+            |panic| {
+                let message = panic_message(panic.as_ref());
+                error!("Parser panicked: {}", message);
+                quarantine_page(&share_isin.isin.to_string(), &res_txt, &message);
+                ScrapingError::ParsingErr
+            },
+        );
+        let _ = sender.send(res);
     });
 
-    receiver.await.unwrap()
+    receiver.await.unwrap_or(Err(ScrapingError::ParsingErr))
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_owned()
+    }
+}
+
+// with the `streaming` feature the page is extracted without building a DOM,
+// falling back to the DOM if the extraction fails
+pub fn parse_document(res_txt: &str, share_isin: &ShareIsin) -> (Share, Vec<ParseFailure>) {
+    #[cfg(feature = "streaming")]
+    match parse_streamed(res_txt, share_isin) {
+        Ok(res) => return res,
+        Err(e) => warn!("{}, parsing the DOM", e),
+    }
+
+    parse_dom(res_txt, share_isin)
+}
+
+pub(crate) fn parse_dom(res_txt: &str, share_isin: &ShareIsin) -> (Share, Vec<ParseFailure>) {
+    let doc = Html::parse_document(res_txt);
+    parse_selector(share_isin, &PropertySelector::new(&doc))
+}
+
+#[cfg(feature = "streaming")]
+pub(crate) fn parse_streamed(
+    res_txt: &str,
+    share_isin: &ShareIsin,
+) -> Result<(Share, Vec<ParseFailure>), streaming::StreamingError> {
+    let mappings = &mappings::field_mappings().share;
+    let page = streaming::StreamedPage::extract(res_txt, mappings)?;
+    Ok(parse_selector(
+        share_isin,
+        &PropertySelector::streamed(&page, mappings),
+    ))
+}
+
+fn parse_selector(
+    share_isin: &ShareIsin,
+    selector: &PropertySelector,
+) -> (Share, Vec<ParseFailure>) {
+    let mut failures = Vec::new();
+    let share = Share::from_selector(share_isin, selector, &mut failures);
+    if !failures.is_empty() {
+        warn!("{} fields failed to parse", failures.len());
+    }
+
+    (share, failures)
 }
 // fn parse_page(res_txt: String, share_isin: &ShareIsin) -> Share {
 //     let doc = Html::parse_document(&res_txt);
@@ -143,3 +236,59 @@ async fn parse_page(res_txt: String, share_isin: &ShareIsin) -> Share {
 //
 //     Share::from_selector(share_isin, &selector)
 // }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use crate::quarantine::{init_quarantine, quarantine_dir};
+
+    use super::*;
+
+    const PAGE: &str = "<html><body><table><tr><td>Prezzo</td></tr></table></body></html>";
+
+    fn panicking_parser(_: &str, _: &ShareIsin) -> (Share, Vec<ParseFailure>) {
+        panic!("unexpected layout");
+    }
+
+    #[tokio::test]
+    async fn quarantines_pages_that_panic() {
+        let dir = std::env::temp_dir().join(format!("quarantine_test_{}", std::process::id()));
+        init_quarantine(dir.clone());
+        assert_eq!(quarantine_dir(), dir);
+
+        let share_isin = ShareIsin::new("ENEL".to_owned(), "IT0003128367".to_owned()).unwrap();
+        let res = parse_page_with(PAGE.to_owned(), &share_isin, panicking_parser).await;
+        assert!(matches!(res, Err(ScrapingError::ParsingErr)), "{:?}", res);
+
+        let mut files: Vec<PathBuf> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        files.sort();
+
+        let names: Vec<&str> = files
+            .iter()
+            .map(|file| file.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(names.len(), 2, "{:?}", names);
+        let stem = names[0].strip_suffix(".html").unwrap();
+        assert_eq!(names[1], format!("{}.json", stem));
+        // <ISIN>_<timestamp>
+        let (isin, timestamp) = stem.split_once('_').unwrap();
+        assert_eq!(isin, "IT0003128367");
+        assert!(
+            chrono::NaiveDateTime::parse_from_str(timestamp, "%Y%m%dT%H%M%S%.3f").is_ok(),
+            "{}",
+            timestamp
+        );
+
+        assert_eq!(fs::read_to_string(&files[0]).unwrap(), PAGE);
+        let details: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&files[1]).unwrap()).unwrap();
+        assert_eq!(details["isin"], "IT0003128367");
+        assert_eq!(details["error"], "unexpected layout");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
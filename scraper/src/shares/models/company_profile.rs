@@ -0,0 +1,51 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use scraper_derive::Scrapable;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::prelude::FromRow;
+use sqlx::Row;
+
+use crate::shares::parsers;
+
+#[derive(Debug, Serialize, Deserialize, Scrapable)]
+pub struct CompanyProfile {
+    pub isin: String,
+    #[scrape(label = "ragione sociale", label = "denominazione")]
+    pub ragione_sociale: Option<String>,
+    #[scrape(label = "indirizzo", label = "sede legale")]
+    pub indirizzo: Option<String>,
+    #[scrape(label = "sito web", label = "sito internet")]
+    pub sito_web: Option<String>,
+    #[scrape(
+        label = "descrizione",
+        label = "attività",
+        parser = parsers::parse_joined_text
+    )]
+    pub descrizione: Option<String>,
+    #[scrape(label = "numero azioni", label = "azioni in circolazione")]
+    pub azioni_in_circolazione: Option<u64>,
+    #[scrape(label = "flottante", label = "free float")]
+    pub flottante: Option<Decimal>,
+    #[scrape(label = "data di quotazione", label = "data inizio quotazione")]
+    pub data_quotazione: Option<NaiveDate>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl FromRow<'_, PgRow> for CompanyProfile {
+    fn from_row(row: &'_ PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            isin: row.try_get("isin")?,
+            ragione_sociale: row.try_get("ragione_sociale")?,
+            indirizzo: row.try_get("indirizzo")?,
+            sito_web: row.try_get("sito_web")?,
+            descrizione: row.try_get("descrizione")?,
+            azioni_in_circolazione: row
+                .try_get::<Option<i64>, _>("azioni_in_circolazione")?
+                .map(|v| v as u64),
+            flottante: row.try_get("flottante")?,
+            data_quotazione: row.try_get("data_quotazione")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
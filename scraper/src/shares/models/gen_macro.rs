@@ -1,30 +1,57 @@
-pub use crate::isins::types::ShareIsin;
-pub use crate::shares::models::ScrapableStruct;
-pub use crate::shares::parsers::SafeParse;
-pub use crate::shares::property_selector::PropertySelector;
-pub use chrono::NaiveDateTime;
-pub use tracing::warn;
-
+// implements serde and sqlx (TEXT) support for enums stored by their label,
+// the enum must implement `Display` and `From<&str>`
 #[macro_export]
-macro_rules! generate_scrapable_struct {
-    ($struct_name:ident, { $($field_name:ident: $field_type:ty),* $(,)? }) => {
-        impl ScrapableStruct for $struct_name {
-            fn from_selector(share_isin: &ShareIsin, selector: &PropertySelector) -> Self {
-                Self {
-                    isin: share_isin.isin.to_string(),
-                    updated_at: chrono::offset::Utc::now().naive_utc(),
-                    $(
-                        $field_name: selector.get_property(stringify!($field_name)).map(|el| el.safe_parse()).flatten(),
-                    )*
-                }
+macro_rules! generate_text_enum_impls {
+    ($enum_name:ident) => {
+        impl std::str::FromStr for $enum_name {
+            type Err = std::convert::Infallible;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Ok(Self::from(s))
+            }
+        }
+
+        impl serde::Serialize for $enum_name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $enum_name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let label = String::deserialize(deserializer)?;
+                Ok(Self::from(label.as_str()))
+            }
+        }
+
+        impl sqlx::Type<sqlx::Postgres> for $enum_name {
+            fn type_info() -> sqlx::postgres::PgTypeInfo {
+                <String as sqlx::Type<sqlx::Postgres>>::type_info()
             }
-            fn with_isin(share_isin: &ShareIsin) -> $struct_name {
-                warn!("Creating empty {}", stringify!($struct_name));
-                $struct_name {
-                    isin: share_isin.isin.to_string(),
-                    $($field_name: None,)*
-                    updated_at: chrono::offset::Utc::now().naive_utc(),
-                }
+
+            fn compatible(ty: &sqlx::postgres::PgTypeInfo) -> bool {
+                <String as sqlx::Type<sqlx::Postgres>>::compatible(ty)
+            }
+        }
+
+        impl<'r> sqlx::Decode<'r, sqlx::Postgres> for $enum_name {
+            fn decode(
+                value: sqlx::postgres::PgValueRef<'r>,
+            ) -> Result<Self, sqlx::error::BoxDynError> {
+                let label = <&str as sqlx::Decode<sqlx::Postgres>>::decode(value)?;
+                Ok(Self::from(label))
+            }
+        }
+
+        impl<'a, V: $crate::shares::parsers::ValueText<'a>>
+            $crate::shares::parsers::TryParse<$enum_name> for V
+        {
+            fn try_parse(
+                &self,
+                field: &str,
+            ) -> Result<$enum_name, $crate::shares::parsers::ParseFailure> {
+                $crate::shares::parsers::element_text(self, field)
+                    .map(|text| $enum_name::from(text.trim()))
             }
         }
     };
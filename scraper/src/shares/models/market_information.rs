@@ -1,20 +1,19 @@
-use super::gen_macro::*;
-use crate::generate_scrapable_struct;
+use super::market_segment::MarketSegment;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use scraper_derive::Scrapable;
 use serde::{Deserialize, Serialize};
 
-#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize, Scrapable)]
 pub struct MarketInformation {
     pub isin: String,
+    #[scrape(label = "super sector")]
     pub super_sector: Option<String>,
-    pub mercato_segmento: Option<String>,
-    pub capitalizzazione_di_mercato: Option<f64>,
+    #[scrape(label = "mercato/segmento")]
+    pub mercato_segmento: Option<MarketSegment>,
+    #[scrape(label = "capitalizzazione di mercato")]
+    pub capitalizzazione_di_mercato: Option<Decimal>,
+    #[scrape(label = "lotto minimo")]
     pub lotto_minimo: Option<f64>,
-    pub updated_at: NaiveDateTime,
+    pub updated_at: DateTime<Utc>,
 }
-
-generate_scrapable_struct!(MarketInformation, {
-    super_sector: String ,
-    mercato_segmento: String,
-    capitalizzazione_di_mercato: f64,
-    lotto_minimo: f64,
-});
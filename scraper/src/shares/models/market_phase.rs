@@ -0,0 +1,62 @@
+use std::fmt::{Display, Formatter};
+
+use crate::generate_text_enum_impls;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MarketPhase {
+    PreOpening,
+    OpeningAuction,
+    ContinuousTrading,
+    VolatilityAuction,
+    ClosingAuction,
+    TradingAtLast,
+    Closed,
+    Suspended,
+    Unknown(String),
+}
+
+impl MarketPhase {
+    pub fn label(&self) -> &str {
+        match self {
+            MarketPhase::PreOpening => "Pre-Apertura",
+            MarketPhase::OpeningAuction => "Asta di Apertura",
+            MarketPhase::ContinuousTrading => "Negoziazione Continua",
+            MarketPhase::VolatilityAuction => "Asta di Volatilità",
+            MarketPhase::ClosingAuction => "Asta di Chiusura",
+            MarketPhase::TradingAtLast => "Trading at Last",
+            MarketPhase::Closed => "Chiusura",
+            MarketPhase::Suspended => "Sospeso",
+            MarketPhase::Unknown(label) => label,
+        }
+    }
+}
+
+impl From<&str> for MarketPhase {
+    fn from(text: &str) -> Self {
+        let normalized = text
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+
+        match normalized.as_str() {
+            "pre-apertura" | "preapertura" | "pre apertura" => MarketPhase::PreOpening,
+            "asta di apertura" | "apertura" => MarketPhase::OpeningAuction,
+            "negoziazione continua" | "continua" => MarketPhase::ContinuousTrading,
+            "asta di volatilità" | "asta di volatilita" => MarketPhase::VolatilityAuction,
+            "asta di chiusura" => MarketPhase::ClosingAuction,
+            "trading at last" | "negoziazione al prezzo di chiusura" => MarketPhase::TradingAtLast,
+            "chiusura" | "chiuso" | "mercato chiuso" => MarketPhase::Closed,
+            "sospeso" | "sospesa" | "sospensione" => MarketPhase::Suspended,
+            _ => MarketPhase::Unknown(text.trim().to_owned()),
+        }
+    }
+}
+
+impl Display for MarketPhase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+generate_text_enum_impls!(MarketPhase);
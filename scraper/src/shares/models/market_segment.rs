@@ -0,0 +1,69 @@
+use std::fmt::{Display, Formatter};
+
+use crate::generate_text_enum_impls;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MarketSegment {
+    BlueChip,
+    Star,
+    Standard,
+    GrowthMilan,
+    Miv,
+    Unknown(String),
+}
+
+impl MarketSegment {
+    pub fn label(&self) -> &str {
+        match self {
+            MarketSegment::BlueChip => "Blue Chip",
+            MarketSegment::Star => "STAR",
+            MarketSegment::Standard => "Standard",
+            MarketSegment::GrowthMilan => "Euronext Growth Milan",
+            MarketSegment::Miv => "MIV",
+            MarketSegment::Unknown(label) => label,
+        }
+    }
+}
+
+impl From<&str> for MarketSegment {
+    fn from(text: &str) -> Self {
+        // the page shows both market and segment (e.g. "EXM / STAR"),
+        // the segment is more specific so it's checked first
+        let parts: Vec<String> = text
+            .split('/')
+            .map(|part| {
+                part.split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .to_lowercase()
+            })
+            .collect();
+        let has_part = |candidates: &[&str]| {
+            parts
+                .iter()
+                .any(|part| candidates.iter().any(|c| part == c))
+        };
+
+        if has_part(&["star", "euronext star milan"]) {
+            MarketSegment::Star
+        } else if has_part(&["blue chip", "bluechip"]) {
+            MarketSegment::BlueChip
+        } else if has_part(&["standard"]) {
+            MarketSegment::Standard
+        } else if has_part(&["egm", "aim", "euronext growth milan", "aim italia"]) {
+            MarketSegment::GrowthMilan
+        } else if has_part(&["miv", "euronext miv milan"]) {
+            MarketSegment::Miv
+        } else {
+            MarketSegment::Unknown(text.trim().to_owned())
+        }
+    }
+}
+
+impl Display for MarketSegment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+generate_text_enum_impls!(MarketSegment);
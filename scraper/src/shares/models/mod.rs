@@ -1,29 +1,47 @@
+pub mod company_profile;
 mod gen_macro;
 mod market_information;
+pub mod market_phase;
+pub mod market_segment;
+pub mod order_book;
 mod performance_metrics;
-mod price_data;
+pub mod price_data;
 pub mod share;
 mod share_details;
 
-use chrono::{NaiveDate, NaiveDateTime};
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
-use super::property_selector::PropertySelector;
+use super::{mappings::DefaultMapping, parsers::ParseFailure, property_selector::PropertySelector};
 use crate::isins::types::ShareIsin;
 
 pub trait ScrapableStruct {
-    fn from_selector(share_isin: &ShareIsin, selector: &PropertySelector) -> Self;
+    // the labels declared with #[scrape(...)], overridable from the mappings file
+    fn default_mappings() -> Vec<DefaultMapping>;
+    // the fields read from the page, each one needs a mapping
+    fn fields() -> Vec<&'static str> {
+        Self::default_mappings()
+            .iter()
+            .map(|mapping| mapping.field)
+            .collect()
+    }
+    fn from_selector(
+        share_isin: &ShareIsin,
+        selector: &PropertySelector,
+        failures: &mut Vec<ParseFailure>,
+    ) -> Self;
     fn with_isin(share_isin: &ShareIsin) -> Self;
 }
 
 #[derive(Default, Debug, Serialize, Deserialize, Clone)]
 pub struct PriceDateReference {
-    pub price: Option<f64>,
+    pub price: Option<Decimal>,
     pub date: Option<NaiveDate>,
 }
 
 #[derive(Default, Debug, Serialize, Deserialize, Clone)]
 pub struct PriceDateTimeReference {
-    pub price: Option<f64>,
-    pub datetime: Option<NaiveDateTime>,
+    pub price: Option<Decimal>,
+    pub datetime: Option<DateTime<Utc>>,
 }
@@ -0,0 +1,409 @@
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::{isins::types::ShareIsin, shares::numbers::parse_number};
+
+static TABLE_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("table").unwrap());
+static ROW_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("tr").unwrap());
+static HEADER_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("th").unwrap());
+static CELL_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("td").unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BookSide {
+    Bid,
+    Ask,
+}
+
+impl BookSide {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BookSide::Bid => "bid",
+            BookSide::Ask => "ask",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderBookLevel {
+    // 1 is the best level
+    pub level: u16,
+    pub price: Decimal,
+    pub quantity: u64,
+    pub orders: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderBook {
+    pub isin: String,
+    pub bids: Vec<OrderBookLevel>,
+    pub asks: Vec<OrderBookLevel>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// column positions of the book table, found from its header
+struct BookColumns {
+    bid_orders: Option<usize>,
+    bid_quantity: usize,
+    bid_price: usize,
+    ask_price: usize,
+    ask_quantity: usize,
+    ask_orders: Option<usize>,
+}
+
+impl BookColumns {
+    // the book table has "N. Ordini | Quantità | Denaro | Lettera | Quantità | N. Ordini"
+    fn from_headers(headers: &[String]) -> Option<Self> {
+        let bid_price = headers.iter().position(|h| h.contains("denaro"))?;
+        let ask_price = headers.iter().position(|h| h.contains("lettera"))?;
+
+        // bid columns are left of the bid price, ask columns right of the ask price
+        let before_bid = |term: &str| (0..bid_price).rev().find(|i| headers[*i].contains(term));
+        let after_ask =
+            |term: &str| (ask_price + 1..headers.len()).find(|i| headers[*i].contains(term));
+
+        Some(Self {
+            bid_orders: before_bid("ord"),
+            bid_quantity: before_bid("quantit")?,
+            bid_price,
+            ask_price,
+            ask_quantity: after_ask("quantit")?,
+            ask_orders: after_ask("ord"),
+        })
+    }
+}
+
+impl OrderBook {
+    pub fn empty(share_isin: &ShareIsin) -> Self {
+        Self {
+            isin: share_isin.isin.to_string(),
+            bids: Vec::new(),
+            asks: Vec::new(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub fn best_bid(&self) -> Option<&OrderBookLevel> {
+        self.bids.first()
+    }
+
+    pub fn best_ask(&self) -> Option<&OrderBookLevel> {
+        self.asks.first()
+    }
+
+    pub fn spread(&self) -> Option<Decimal> {
+        Some(self.best_ask()?.price - self.best_bid()?.price)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bids.is_empty() && self.asks.is_empty()
+    }
+
+    pub fn from_document(share_isin: &ShareIsin, document: &Html) -> Option<Self> {
+        let (table, columns) = document.select(&TABLE_SELECTOR).find_map(|table| {
+            let headers: Vec<String> = table
+                .select(&HEADER_SELECTOR)
+                .map(|th| th.text().collect::<String>().trim().to_lowercase())
+                .collect();
+            BookColumns::from_headers(&headers).map(|columns| (table, columns))
+        })?;
+
+        let rows = table.select(&ROW_SELECTOR).map(|row| {
+            row.select(&CELL_SELECTOR)
+                .map(|cell| cell.text().collect::<String>())
+                .collect::<Vec<_>>()
+        });
+
+        Some(Self::from_rows(share_isin, &columns, rows))
+    }
+
+    // `tables` are the headers and the cell texts of each row
+    #[cfg(feature = "streaming")]
+    pub(crate) fn from_tables<'a>(
+        share_isin: &ShareIsin,
+        tables: impl IntoIterator<Item = (&'a [String], &'a [Vec<String>])>,
+    ) -> Option<Self> {
+        let (rows, columns) = tables.into_iter().find_map(|(headers, rows)| {
+            let headers: Vec<String> = headers
+                .iter()
+                .map(|header| header.trim().to_lowercase())
+                .collect();
+            BookColumns::from_headers(&headers).map(|columns| (rows, columns))
+        })?;
+
+        Some(Self::from_rows(share_isin, &columns, rows))
+    }
+
+    fn from_rows<R: AsRef<[String]>>(
+        share_isin: &ShareIsin,
+        columns: &BookColumns,
+        rows: impl IntoIterator<Item = R>,
+    ) -> Self {
+        let mut book = Self::empty(share_isin);
+
+        for cells in rows {
+            let cells = cells.as_ref();
+            if cells.is_empty() {
+                continue;
+            }
+
+            let bid_columns = (columns.bid_price, columns.bid_quantity, columns.bid_orders);
+            if let Some(level) = parse_level(cells, book.bids.len() + 1, bid_columns) {
+                book.bids.push(level);
+            }
+            let ask_columns = (columns.ask_price, columns.ask_quantity, columns.ask_orders);
+            if let Some(level) = parse_level(cells, book.asks.len() + 1, ask_columns) {
+                book.asks.push(level);
+            }
+        }
+
+        debug!(
+            "Found order book with {} bids and {} asks",
+            book.bids.len(),
+            book.asks.len()
+        );
+
+        book
+    }
+}
+
+fn parse_level(
+    cells: &[String],
+    level: usize,
+    (price, quantity, orders): (usize, usize, Option<usize>),
+) -> Option<OrderBookLevel> {
+    let number = |i: usize| {
+        let text = cells.get(i)?.trim();
+        parse_number(text)
+            .inspect_err(|e| {
+                // empty levels are shown as "-"
+                if text != "-" && !text.is_empty() {
+                    warn!("Invalid order book value {:?}: {}", text, e);
+                }
+            })
+            .ok()
+            .map(|n| n.value)
+    };
+
+    Some(OrderBookLevel {
+        level: level as u16,
+        price: number(price)?,
+        quantity: number(quantity)?.to_u64()?,
+        orders: orders.and_then(number).and_then(|n| n.to_u64()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn headers(headers: &[&str]) -> Vec<String> {
+        headers.iter().map(|header| header.to_string()).collect()
+    }
+
+    fn columns(
+        columns: &BookColumns,
+    ) -> (Option<usize>, usize, usize, usize, usize, Option<usize>) {
+        (
+            columns.bid_orders,
+            columns.bid_quantity,
+            columns.bid_price,
+            columns.ask_price,
+            columns.ask_quantity,
+            columns.ask_orders,
+        )
+    }
+
+    fn rows(rows: &[&[&str]]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|cells| cells.iter().map(|cell| cell.to_string()).collect())
+            .collect()
+    }
+
+    fn level(level: u16, price: &str, quantity: u64, orders: Option<u64>) -> OrderBookLevel {
+        OrderBookLevel {
+            level,
+            price: Decimal::from_str(price).unwrap(),
+            quantity,
+            orders,
+        }
+    }
+
+    fn share_isin() -> ShareIsin {
+        ShareIsin::new("ENEL".to_owned(), "IT0003128367".to_owned()).unwrap()
+    }
+
+    const HEADERS: [&str; 6] = [
+        "n. ordini",
+        "quantità",
+        "denaro",
+        "lettera",
+        "quantità",
+        "n. ordini",
+    ];
+
+    #[test]
+    fn finds_columns_from_headers() {
+        let found = BookColumns::from_headers(&headers(&HEADERS)).unwrap();
+        assert_eq!(columns(&found), (Some(0), 1, 2, 3, 4, Some(5)));
+
+        // without the order counts
+        let found =
+            BookColumns::from_headers(&headers(&["quantità", "denaro", "lettera", "quantità"]))
+                .unwrap();
+        assert_eq!(columns(&found), (None, 0, 1, 2, 3, None));
+
+        // columns outside the book are skipped
+        let found = BookColumns::from_headers(&headers(&[
+            "ora",
+            "n. ordini",
+            "quantità",
+            "denaro prezzo",
+            "lettera prezzo",
+            "quantità",
+            "n. ordini",
+            "quantità totale",
+        ]))
+        .unwrap();
+        assert_eq!(columns(&found), (Some(1), 2, 3, 4, 5, Some(6)));
+    }
+
+    #[test]
+    fn rejects_tables_without_book_columns() {
+        let tables: [&[&str]; 4] = [
+            &["n. ordini", "quantità", "lettera", "quantità", "n. ordini"],
+            &["n. ordini", "quantità", "denaro", "quantità", "n. ordini"],
+            // the quantity of a side must be on its own side
+            &["n. ordini", "denaro", "quantità", "lettera", "n. ordini"],
+            &["n. ordini", "quantità", "denaro", "lettera", "n. ordini"],
+        ];
+
+        for table in tables {
+            assert!(
+                BookColumns::from_headers(&headers(table)).is_none(),
+                "{:?}",
+                table
+            );
+        }
+    }
+
+    #[test]
+    fn extracts_levels() {
+        let columns = BookColumns::from_headers(&headers(&HEADERS)).unwrap();
+        let book = OrderBook::from_rows(
+            &share_isin(),
+            &columns,
+            rows(&[
+                &[],
+                &["3", "1.200", "6,866", "6,868", "900", "2"],
+                &["1", "500", "6,864", "6,87", "2.500", "-"],
+            ]),
+        );
+
+        assert_eq!(
+            book.bids,
+            [
+                level(1, "6.866", 1200, Some(3)),
+                level(2, "6.864", 500, Some(1))
+            ]
+        );
+        assert_eq!(
+            book.asks,
+            [
+                level(1, "6.868", 900, Some(2)),
+                level(2, "6.87", 2500, None)
+            ]
+        );
+        assert_eq!(book.spread(), Decimal::from_str("0.002").ok());
+    }
+
+    #[test]
+    fn extracts_sides_with_fewer_levels() {
+        let columns = BookColumns::from_headers(&headers(&HEADERS)).unwrap();
+        let book = OrderBook::from_rows(
+            &share_isin(),
+            &columns,
+            rows(&[
+                &["3", "1.200", "6,866", "6,868", "900", "2"],
+                &["-", "-", "-", "6,87", "2.500", "4"],
+                // a short row has no ask
+                &["1", "100", "6,86"],
+            ]),
+        );
+
+        assert_eq!(
+            book.bids,
+            [
+                level(1, "6.866", 1200, Some(3)),
+                level(2, "6.86", 100, Some(1))
+            ]
+        );
+        assert_eq!(
+            book.asks,
+            [
+                level(1, "6.868", 900, Some(2)),
+                level(2, "6.87", 2500, Some(4))
+            ]
+        );
+    }
+
+    #[test]
+    fn extracts_books_with_a_missing_side() {
+        let columns = BookColumns::from_headers(&headers(&HEADERS)).unwrap();
+        let book = OrderBook::from_rows(
+            &share_isin(),
+            &columns,
+            rows(&[
+                &["-", "-", "-", "6,868", "900", "2"],
+                &["", "", "", "6,87", "2.500", "4"],
+            ]),
+        );
+        assert!(book.bids.is_empty());
+        assert_eq!(book.asks.len(), 2);
+        assert_eq!(book.best_bid(), None);
+        assert_eq!(book.spread(), None);
+        assert!(!book.is_empty());
+
+        let book = OrderBook::from_rows(
+            &share_isin(),
+            &columns,
+            rows(&[&["3", "1.200", "6,866", "-", "-", "-"]]),
+        );
+        assert_eq!(book.bids, [level(1, "6.866", 1200, Some(3))]);
+        assert!(book.asks.is_empty());
+        assert_eq!(book.spread(), None);
+
+        let book = OrderBook::from_rows(
+            &share_isin(),
+            &columns,
+            rows(&[&["-", "-", "-", "-", "-", "-"]]),
+        );
+        assert!(book.is_empty());
+    }
+
+    #[test]
+    fn finds_the_book_table_in_documents() {
+        let document = Html::parse_document(
+            r#"<table><tr><th>Prezzo</th><th>Quantità</th></tr><tr><td>1</td><td>2</td></tr></table>
+            <table>
+              <tr><th>N. Ordini</th><th>Quantit&agrave;</th><th>Denaro</th>
+                  <th>Lettera</th><th>Quantit&agrave;</th><th>N. Ordini</th></tr>
+              <tr><td>3</td><td>1.200</td><td>6,866</td><td>-</td><td>-</td><td>-</td></tr>
+            </table>"#,
+        );
+
+        let book = OrderBook::from_document(&share_isin(), &document).unwrap();
+        assert_eq!(book.isin, "IT0003128367");
+        assert_eq!(book.bids, [level(1, "6.866", 1200, Some(3))]);
+        assert!(book.asks.is_empty());
+
+        let document = Html::parse_document("<table><tr><th>Prezzo</th></tr></table>");
+        assert!(OrderBook::from_document(&share_isin(), &document).is_none());
+    }
+}
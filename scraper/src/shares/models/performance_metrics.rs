@@ -1,18 +1,16 @@
-use super::gen_macro::*;
-use crate::generate_scrapable_struct;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use scraper_derive::Scrapable;
 use serde::{Deserialize, Serialize};
 
-#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize, Scrapable)]
 pub struct PerformanceMetrics {
     pub isin: String,
-    pub performance_1_mese: Option<f64>,
-    pub performance_6_mesi: Option<f64>,
-    pub performance_1_anno: Option<f64>,
-    pub updated_at: NaiveDateTime,
+    #[scrape(label = "performance 1 mese")]
+    pub performance_1_mese: Option<Decimal>,
+    #[scrape(label = "performance 6 mesi")]
+    pub performance_6_mesi: Option<Decimal>,
+    #[scrape(label = "performance 1 anno")]
+    pub performance_1_anno: Option<Decimal>,
+    pub updated_at: DateTime<Utc>,
 }
-
-generate_scrapable_struct!(PerformanceMetrics, {
-    performance_1_mese: f64,
-    performance_6_mesi: f64,
-    performance_1_anno: f64,
-});
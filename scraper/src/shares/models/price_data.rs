@@ -1,35 +1,53 @@
-use super::gen_macro::*;
-use crate::generate_scrapable_struct;
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use scraper_derive::Scrapable;
 use serde::{Deserialize, Serialize};
 use sqlx::postgres::PgRow;
 use sqlx::prelude::FromRow;
 use sqlx::Row;
 
-use super::{PriceDateReference, PriceDateTimeReference};
+use super::{market_phase::MarketPhase, PriceDateReference, PriceDateTimeReference};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Scrapable)]
 pub struct PriceData {
     pub isin: String,
-    pub fase_di_mercato: Option<String>,
-    pub prezzo_ultimo_contratto: Option<f64>,
-    pub var_percentuale: Option<f64>,
-    pub var_assoluta: Option<f64>,
-    pub pr_medio_progr: Option<f64>,
-    pub data_ora_ultimo_contratto: Option<NaiveDateTime>,
-    pub quantita_ultimo: Option<f64>,
-    pub quantita_totale: Option<f64>,
+    #[scrape(label = "fase di mercato")]
+    pub fase_di_mercato: Option<MarketPhase>,
+    #[scrape(label = "prezzo ultimo contratto")]
+    pub prezzo_ultimo_contratto: Option<Decimal>,
+    #[scrape(label = "var %")]
+    pub var_percentuale: Option<Decimal>,
+    #[scrape(label = "var assoluta")]
+    pub var_assoluta: Option<Decimal>,
+    #[scrape(label = "pr medio progr")]
+    pub pr_medio_progr: Option<Decimal>,
+    #[scrape(label = "data - ora ultimo contratto:")]
+    pub data_ora_ultimo_contratto: Option<DateTime<Utc>>,
+    #[scrape(label = "quantità ultimo")]
+    pub quantita_ultimo: Option<Decimal>,
+    #[scrape(label = "quantità totale")]
+    pub quantita_totale: Option<Decimal>,
+    #[scrape(label = "numero contratti")]
     pub numero_contratti: Option<u64>,
-    pub controvalore: Option<f64>,
-    pub max_oggi: Option<f64>,
+    #[scrape(label = "controvalore")]
+    pub controvalore: Option<Decimal>,
+    #[scrape(label = "max oggi")]
+    pub max_oggi: Option<Decimal>,
+    #[scrape(label = "max anno")]
     pub max_anno: Option<PriceDateReference>,
-    pub min_oggi: Option<f64>,
+    #[scrape(label = "min oggi")]
+    pub min_oggi: Option<Decimal>,
+    #[scrape(label = "min anno")]
     pub min_anno: Option<PriceDateReference>,
-    pub chiusura_precedente: Option<f64>,
+    #[scrape(label = "chiusura precedente/pre-chiusura/chiusura:")]
+    pub chiusura_precedente: Option<Decimal>,
+    #[scrape(label = "prezzo di riferimento")]
     pub prezzo_riferimento: Option<PriceDateTimeReference>,
+    #[scrape(label = "prezzo ufficiale")]
     pub prezzo_ufficiale: Option<PriceDateReference>,
-    pub apertura_odierna: Option<f64>,
-    pub updated_at: NaiveDateTime,
+    #[scrape(label = "apertura odierna:")]
+    pub apertura_odierna: Option<Decimal>,
+    pub updated_at: DateTime<Utc>,
 }
 
 impl FromRow<'_, PgRow> for PriceData {
@@ -72,24 +90,3 @@ impl FromRow<'_, PgRow> for PriceData {
         })
     }
 }
-
-generate_scrapable_struct!(PriceData, {
-    fase_di_mercato: String,
-    prezzo_ultimo_contratto: f64,
-    var_percentuale: f64,
-    var_assoluta: f64,
-    pr_medio_progr: f64,
-    data_ora_ultimo_contratto: NaiveDateTime,
-    quantita_ultimo: f64,
-    quantita_totale: f64,
-    numero_contratti: u64,
-    controvalore: f64,
-    max_oggi: f64,
-    max_anno: PriceDateReference,
-    min_oggi: f64,
-    min_anno: PriceDateReference,
-    chiusura_precedente: f64,
-    prezzo_riferimento: PriceDateTimeReference,
-    prezzo_ufficiale: PriceDateReference,
-    apertura_odierna: f64,
-});
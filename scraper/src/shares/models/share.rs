@@ -1,11 +1,18 @@
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{prelude::FromRow, Row};
-use tracing::info;
+use tracing::{info, warn};
 
 use super::{
-    gen_macro::*, market_information::MarketInformation, performance_metrics::PerformanceMetrics,
-    price_data::PriceData, share_details::ShareDetails,
+    market_information::MarketInformation, order_book::OrderBook,
+    performance_metrics::PerformanceMetrics, price_data::PriceData, share_details::ShareDetails,
+    ScrapableStruct,
+};
+use crate::{
+    isins::types::ShareIsin,
+    shares::{
+        mappings::DefaultMapping, parsers::ParseFailure, property_selector::PropertySelector,
+    },
 };
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -15,10 +22,22 @@ pub struct Share {
     pub market_information: MarketInformation,
     pub price_data: PriceData,
     pub performance_metrics: PerformanceMetrics,
-    pub updated_at: NaiveDateTime,
+    // only available when freshly scraped, the book is stored per snapshot
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_book: Option<OrderBook>,
+    pub updated_at: DateTime<Utc>,
 }
 
 impl ScrapableStruct for Share {
+    fn default_mappings() -> Vec<DefaultMapping> {
+        [
+            ShareDetails::default_mappings(),
+            MarketInformation::default_mappings(),
+            PriceData::default_mappings(),
+            PerformanceMetrics::default_mappings(),
+        ]
+        .concat()
+    }
     fn with_isin(share_isin: &ShareIsin) -> Self {
         warn!("Creating empty share");
         Share {
@@ -27,19 +46,25 @@ impl ScrapableStruct for Share {
             market_information: MarketInformation::with_isin(share_isin),
             price_data: PriceData::with_isin(share_isin),
             performance_metrics: PerformanceMetrics::with_isin(share_isin),
-            updated_at: chrono::offset::Utc::now().naive_utc(),
+            order_book: None,
+            updated_at: Utc::now(),
         }
     }
 
-    fn from_selector(share_isin: &ShareIsin, selector: &PropertySelector) -> Self {
+    fn from_selector(
+        share_isin: &ShareIsin,
+        selector: &PropertySelector,
+        failures: &mut Vec<ParseFailure>,
+    ) -> Self {
         info!("Creating full share from selector");
         Share {
             share_id: share_isin.clone(),
-            share_details: ShareDetails::from_selector(share_isin, selector),
-            market_information: MarketInformation::from_selector(share_isin, selector),
-            price_data: PriceData::from_selector(share_isin, selector),
-            performance_metrics: PerformanceMetrics::from_selector(share_isin, selector),
-            updated_at: chrono::offset::Utc::now().naive_utc(),
+            share_details: ShareDetails::from_selector(share_isin, selector, failures),
+            market_information: MarketInformation::from_selector(share_isin, selector, failures),
+            price_data: PriceData::from_selector(share_isin, selector, failures),
+            performance_metrics: PerformanceMetrics::from_selector(share_isin, selector, failures),
+            order_book: selector.order_book(share_isin),
+            updated_at: Utc::now(),
         }
     }
 }
@@ -58,6 +83,7 @@ impl FromRow<'_, sqlx::postgres::PgRow> for Share {
             market_information,
             price_data,
             performance_metrics,
+            order_book: None,
             updated_at,
         })
     }
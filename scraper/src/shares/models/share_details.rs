@@ -1,16 +1,13 @@
-use super::gen_macro::*;
-use crate::generate_scrapable_struct;
+use chrono::{DateTime, Utc};
+use scraper_derive::Scrapable;
 use serde::{Deserialize, Serialize};
 
-#[derive(Default, sqlx::FromRow, Debug, Serialize, Deserialize)]
+#[derive(Default, sqlx::FromRow, Debug, Serialize, Deserialize, Scrapable)]
 pub struct ShareDetails {
     pub isin: String,
+    #[scrape(label = "id strumento")]
     pub id_strumento: Option<f64>,
+    #[scrape(label = "codice alfanumerico")]
     pub codice_alfanumerico: Option<String>,
-    pub updated_at: NaiveDateTime,
+    pub updated_at: DateTime<Utc>,
 }
-
-generate_scrapable_struct!(ShareDetails, {
-    id_strumento: f64,
-    codice_alfanumerico: String,
-});
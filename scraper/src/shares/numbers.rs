@@ -0,0 +1,391 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+
+// suffixes used by the site for big amounts (e.g. market cap "1.234,5 Mln"),
+// longer ones first so that "milioni" isn't matched as "mil..."
+const UNITS: [(&str, i64); 9] = [
+    ("miliardi", 1_000_000_000),
+    ("milioni", 1_000_000),
+    ("migliaia", 1_000),
+    ("mld", 1_000_000_000),
+    ("mrd", 1_000_000_000),
+    ("mln", 1_000_000),
+    ("migl", 1_000),
+    ("mila", 1_000),
+    ("k", 1_000),
+];
+
+const CURRENCIES: [&str; 3] = ["€", "eur", "euro"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedNumber {
+    pub value: Decimal,
+    pub ambiguity: Option<Ambiguity>,
+}
+
+// a single separator followed by exactly three digits (e.g. "1.234") can be
+// read both ways, `value` always follows the Italian convention
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ambiguity {
+    pub as_thousands: Decimal,
+    pub as_decimal: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NumberError {
+    Empty,
+    InvalidCharacter(char),
+    InvalidGrouping(String),
+    NotUnsignedInteger,
+    Overflow,
+}
+
+impl Display for NumberError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NumberError::Empty => write!(f, "no digits"),
+            NumberError::InvalidCharacter(c) => write!(f, "unexpected character {c:?}"),
+            NumberError::InvalidGrouping(s) => write!(f, "invalid digit grouping in {s:?}"),
+            NumberError::NotUnsignedInteger => write!(f, "not an unsigned integer"),
+            NumberError::Overflow => write!(f, "number too large"),
+        }
+    }
+}
+
+impl std::error::Error for NumberError {}
+
+// parses numbers as shown by borsaitaliana.it with `lang=it`:
+// "." as thousands separator, "," as decimal separator, optional sign,
+// percent, currency symbol and unit multiplier
+pub fn parse_number(text: &str) -> Result<ParsedNumber, NumberError> {
+    let text = normalize_spaces(text);
+    let mut rest = text.trim();
+
+    let mut is_negative = false;
+    if let Some(stripped) = rest.strip_prefix(['-', '−']) {
+        is_negative = true;
+        rest = stripped.trim_start();
+    } else if let Some(stripped) = rest.strip_prefix('+') {
+        rest = stripped.trim_start();
+    }
+
+    rest = strip_currency(rest);
+    rest = rest.strip_suffix('%').unwrap_or(rest).trim_end();
+    rest = strip_currency(rest);
+
+    let (rest, multiplier) = strip_unit(rest);
+    let rest = strip_currency(rest);
+
+    if rest.is_empty() {
+        return Err(NumberError::Empty);
+    }
+
+    let (value, ambiguity) = parse_digits(rest)?;
+
+    let apply = |val: Decimal| -> Result<Decimal, NumberError> {
+        let val = val
+            .checked_mul(Decimal::from(multiplier))
+            .ok_or(NumberError::Overflow)?;
+        Ok(if is_negative { -val } else { val })
+    };
+
+    let ambiguity = match ambiguity {
+        Some(a) => Some(Ambiguity {
+            as_thousands: apply(a.as_thousands)?,
+            as_decimal: apply(a.as_decimal)?,
+        }),
+        None => None,
+    };
+
+    Ok(ParsedNumber {
+        value: apply(value)?,
+        ambiguity,
+    })
+}
+
+fn normalize_spaces(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\u{a0}' | '\u{202f}' | '\u{2009}' | '\t' | '\n' | '\r' => ' ',
+            c => c,
+        })
+        .collect()
+}
+
+fn strip_currency(text: &str) -> &str {
+    let mut text = text.trim();
+    for currency in CURRENCIES {
+        if let Some(rest) = strip_prefix_ignore_case(text, currency) {
+            // "euro" must not eat the start of a word
+            if !rest.starts_with(|c: char| c.is_alphabetic()) {
+                text = rest.trim_start();
+            }
+        }
+        if let Some(rest) = strip_suffix_ignore_case(text, currency) {
+            if !rest.ends_with(|c: char| c.is_alphabetic()) {
+                text = rest.trim_end();
+            }
+        }
+    }
+    text
+}
+
+fn strip_unit(text: &str) -> (&str, i64) {
+    let trimmed = text.trim_end().trim_end_matches('.');
+    for (unit, multiplier) in UNITS {
+        if let Some(rest) = strip_suffix_ignore_case(trimmed, unit) {
+            // the unit must be a separate word or follow the digits directly
+            if rest.is_empty() || rest.ends_with(|c: char| c.is_ascii_digit() || c == ' ') {
+                return (rest.trim_end(), multiplier);
+            }
+        }
+    }
+    (text, 1)
+}
+
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = text.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix)
+        .then(|| &text[prefix.len()..])
+}
+
+fn strip_suffix_ignore_case<'a>(text: &'a str, suffix: &str) -> Option<&'a str> {
+    let start = text.len().checked_sub(suffix.len())?;
+    let tail = text.get(start..)?;
+    tail.eq_ignore_ascii_case(suffix).then(|| &text[..start])
+}
+
+fn parse_digits(text: &str) -> Result<(Decimal, Option<Ambiguity>), NumberError> {
+    // spaces are only valid as thousands separators ("1 234 567")
+    let compact: String = text.chars().filter(|c| *c != ' ').collect();
+
+    if let Some(c) = compact
+        .chars()
+        .find(|c| !c.is_ascii_digit() && *c != '.' && *c != ',')
+    {
+        return Err(NumberError::InvalidCharacter(c));
+    }
+    if !compact.chars().any(|c| c.is_ascii_digit()) {
+        return Err(NumberError::Empty);
+    }
+
+    let dots = compact.matches('.').count();
+    let commas = compact.matches(',').count();
+
+    match (dots, commas) {
+        (0, 0) => Ok((to_decimal(&compact)?, None)),
+        // "1.234,56" (Italian) or "1,234.56" (English), the last one is the decimal separator
+        (_, _) if dots > 0 && commas > 0 => {
+            let last_dot = compact.rfind('.').unwrap();
+            let last_comma = compact.rfind(',').unwrap();
+            let (thousands, decimal) = if last_comma > last_dot {
+                ('.', ',')
+            } else {
+                (',', '.')
+            };
+            if compact.matches(decimal).count() > 1 {
+                return Err(NumberError::InvalidGrouping(text.to_owned()));
+            }
+            let (int_part, frac_part) = compact.split_once(decimal).unwrap();
+            let int_part = ungroup(int_part, thousands, text)?;
+            Ok((to_decimal(&format!("{int_part}.{frac_part}"))?, None))
+        }
+        // "1.234.567" or "1,234,567", repeated separators can only be thousands
+        (d, c) if d > 1 || c > 1 => {
+            let separator = if d > 1 { '.' } else { ',' };
+            Ok((to_decimal(&ungroup(&compact, separator, text)?)?, None))
+        }
+        // a single "." or ","
+        _ => {
+            let separator = if dots == 1 { '.' } else { ',' };
+            let (int_part, frac_part) = compact.split_once(separator).unwrap();
+
+            let as_decimal = to_decimal(&format!("{int_part}.{frac_part}"))?;
+            let could_be_thousands = frac_part.len() == 3
+                && (1..=3).contains(&int_part.len())
+                && !int_part.starts_with('0');
+
+            if !could_be_thousands {
+                // "," is always decimal, "." is decimal when it can't be a group
+                return Ok((as_decimal, None));
+            }
+
+            let as_thousands = to_decimal(&format!("{int_part}{frac_part}"))?;
+            let value = if separator == '.' {
+                as_thousands
+            } else {
+                as_decimal
+            };
+
+            Ok((
+                value,
+                Some(Ambiguity {
+                    as_thousands,
+                    as_decimal,
+                }),
+            ))
+        }
+    }
+}
+
+// removes thousands separators, checking that groups have three digits
+fn ungroup(int_part: &str, separator: char, original: &str) -> Result<String, NumberError> {
+    let mut groups = int_part.split(separator);
+    let first = groups.next().unwrap_or_default();
+
+    if first.is_empty() || (first.len() > 3 && int_part.contains(separator)) {
+        return Err(NumberError::InvalidGrouping(original.to_owned()));
+    }
+
+    let mut res = first.to_owned();
+    for group in groups {
+        if group.len() != 3 {
+            return Err(NumberError::InvalidGrouping(original.to_owned()));
+        }
+        res.push_str(group);
+    }
+
+    Ok(res)
+}
+
+fn to_decimal(text: &str) -> Result<Decimal, NumberError> {
+    let text = text.strip_suffix('.').unwrap_or(text);
+    Decimal::from_str(text).map_err(|_| NumberError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    // strings as they appear on the dati-completi and listing pages
+    const UNAMBIGUOUS: &[(&str, &str)] = &[
+        // prices
+        ("0,1235", "0.1235"),
+        ("1,5", "1.5"),
+        ("12,34", "12.34"),
+        ("123,4567", "123.4567"),
+        ("1.234,56", "1234.56"),
+        ("12.345,6789", "12345.6789"),
+        ("0,0004", "0.0004"),
+        ("3", "3"),
+        ("1000", "1000"),
+        // variations
+        ("+1,23", "1.23"),
+        ("-0,45", "-0.45"),
+        ("+0,00", "0.00"),
+        ("−2,1", "-2.1"),
+        ("+1,23%", "1.23"),
+        ("-12,5 %", "-12.5"),
+        ("+ 3,40%", "3.40"),
+        // volumes and turnover
+        ("1.234.567", "1234567"),
+        ("12.345.678", "12345678"),
+        ("12.345.678,90", "12345678.90"),
+        ("1 234 567", "1234567"),
+        ("1\u{a0}234\u{a0}567", "1234567"),
+        ("1\u{202f}234,5", "1234.5"),
+        ("0.5", "0.5"),
+        ("12.34", "12.34"),
+        ("1234.5678", "1234.5678"),
+        // currencies
+        ("€ 15,20", "15.20"),
+        ("15,20 €", "15.20"),
+        ("15,20€", "15.20"),
+        ("EUR 1.234.567,00", "1234567.00"),
+        ("1.234.567,00 Euro", "1234567.00"),
+        ("-€ 0,35", "-0.35"),
+        // units
+        ("1.234,5 Mln", "1234500000"),
+        ("1.234,5 Mln €", "1234500000"),
+        ("€ 2,35 Mld", "2350000000"),
+        ("2,35 mld", "2350000000"),
+        ("2,35Mld", "2350000000"),
+        ("12,3 Migl.", "12300"),
+        ("150 mila", "150000"),
+        ("3,5 milioni", "3500000"),
+        ("1,2 miliardi", "1200000000"),
+        ("850 K", "850000"),
+        // english formatting (lang=en pages)
+        ("1,234,567", "1234567"),
+        ("1,234.56", "1234.56"),
+        // whitespace around the value
+        ("  0,1235\n", "0.1235"),
+        ("\t+1,23 %\t", "1.23"),
+    ];
+
+    // (text, value following the Italian convention, thousands reading, decimal reading)
+    const AMBIGUOUS: &[(&str, &str, &str, &str)] = &[
+        ("1.234", "1234", "1234", "1.234"),
+        ("12.345", "12345", "12345", "12.345"),
+        ("999.999", "999999", "999999", "999.999"),
+        ("1,234", "1.234", "1234", "1.234"),
+        ("-1.500", "-1500", "-1500", "-1.500"),
+        ("1.500 Mln", "1500000000", "1500000000", "1500000"),
+    ];
+
+    const INVALID: &[(&str, NumberError)] = &[
+        ("", NumberError::Empty),
+        ("   ", NumberError::Empty),
+        ("-", NumberError::Empty),
+        ("%", NumberError::Empty),
+        ("€", NumberError::Empty),
+        ("Mln", NumberError::Empty),
+        ("n.d.", NumberError::InvalidCharacter('n')),
+        ("abc", NumberError::InvalidCharacter('a')),
+        ("12,34abc", NumberError::InvalidCharacter('a')),
+        ("1.23.456", NumberError::InvalidGrouping(String::new())),
+        ("1.2345,6", NumberError::InvalidGrouping(String::new())),
+        ("1,23,4", NumberError::InvalidGrouping(String::new())),
+        ("1,2.3,4", NumberError::InvalidGrouping(String::new())),
+    ];
+
+    #[test]
+    fn parses_unambiguous_numbers() {
+        for (text, expected) in UNAMBIGUOUS {
+            let parsed = parse_number(text).unwrap_or_else(|e| panic!("{text:?}: {e}"));
+            assert_eq!(parsed.value, dec(expected), "value of {text:?}");
+            assert_eq!(parsed.ambiguity, None, "ambiguity of {text:?}");
+        }
+    }
+
+    #[test]
+    fn reports_ambiguous_numbers() {
+        for (text, expected, as_thousands, as_decimal) in AMBIGUOUS {
+            let parsed = parse_number(text).unwrap_or_else(|e| panic!("{text:?}: {e}"));
+            assert_eq!(parsed.value, dec(expected), "value of {text:?}");
+            assert_eq!(
+                parsed.ambiguity,
+                Some(Ambiguity {
+                    as_thousands: dec(as_thousands),
+                    as_decimal: dec(as_decimal),
+                }),
+                "ambiguity of {text:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_numbers() {
+        for (text, expected) in INVALID {
+            let err = parse_number(text).expect_err(text);
+            match (expected, err) {
+                (NumberError::InvalidGrouping(_), NumberError::InvalidGrouping(_)) => {}
+                (expected, err) => assert_eq!(&err, expected, "error of {text:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_exact_scale() {
+        let parsed = parse_number("0,1235").unwrap();
+        assert_eq!(parsed.value.to_string(), "0.1235");
+        let parsed = parse_number("1.234,500").unwrap();
+        assert_eq!(parsed.value.to_string(), "1234.500");
+    }
+}
@@ -0,0 +1,54 @@
+use scraper::Html;
+use serde::Serialize;
+
+use super::{
+    models::ScrapableStruct,
+    parsers::{element_text, ParseFailure},
+    property_selector::PropertySelector,
+    Share,
+};
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum FieldOutcome {
+    Parsed,
+    // the label was found but the site shows a placeholder
+    NoValue,
+    // no label (or value element) on the page
+    NotFound,
+    Failed { raw_text: String, reason: String },
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldReport {
+    pub field: &'static str,
+    #[serde(flatten)]
+    pub outcome: FieldOutcome,
+}
+
+// how each share field was extracted, `failures` are the ones returned with the share
+pub fn share_field_outcomes(res_txt: &str, failures: &[ParseFailure]) -> Vec<FieldReport> {
+    let doc = Html::parse_document(res_txt);
+    let selector = PropertySelector::new(&doc);
+
+    Share::fields()
+        .into_iter()
+        .map(|field| {
+            let outcome = match failures.iter().find(|f| f.field == field) {
+                Some(failure) => FieldOutcome::Failed {
+                    raw_text: failure.raw_text.clone(),
+                    reason: failure.reason.to_string(),
+                },
+                None => match selector.get_property(field) {
+                    None => FieldOutcome::NotFound,
+                    Some(el) => match element_text(&el, field) {
+                        Err(failure) if failure.is_no_value() => FieldOutcome::NoValue,
+                        _ => FieldOutcome::Parsed,
+                    },
+                },
+            };
+
+            FieldReport { field, outcome }
+        })
+        .collect()
+}
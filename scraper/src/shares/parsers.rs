@@ -1,152 +1,639 @@
-use std::num::{ParseFloatError, ParseIntError};
+use std::fmt::{Display, Formatter};
 
-use chrono::{NaiveDate, NaiveDateTime};
-use once_cell::sync::Lazy;
-use regex::Regex;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use chrono_tz::Europe::Rome;
+use rust_decimal::{prelude::ToPrimitive, Decimal};
 use scraper::ElementRef;
+use serde::Serialize;
+use tracing::{debug, warn};
 
-use super::models::{PriceDateReference, PriceDateTimeReference};
+use super::{
+    models::{PriceDateReference, PriceDateTimeReference},
+    numbers::{parse_number, NumberError},
+};
 
-static DOTS_AS_THOUSANDS_SEPARATOR_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"^(\d{1,3})(\.?\d{3})*(,\d+)?$").unwrap());
+// values shown by the site when a field has no data
+const NO_VALUE_PLACEHOLDERS: [&str; 4] = ["", "-", "--", "n.d."];
 
-pub trait DefaultParse<T> {
-    fn default_parse(&self) -> T;
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum ParseFailureReason {
+    MissingText,
+    NoValue,
+    InvalidNumber(String),
+    InvalidDate(String),
 }
 
-pub trait SafeParse<T> {
-    fn safe_parse(&self) -> Option<T>;
+impl Display for ParseFailureReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseFailureReason::MissingText => write!(f, "element has no text"),
+            ParseFailureReason::NoValue => write!(f, "no value available"),
+            ParseFailureReason::InvalidNumber(e) => write!(f, "invalid number ({e})"),
+            ParseFailureReason::InvalidDate(e) => write!(f, "invalid date ({e})"),
+        }
+    }
 }
 
-impl SafeParse<f64> for ElementRef<'_> {
-    fn safe_parse(&self) -> Option<f64> {
-        self.text().next().and_then(|text| parse_float(text).ok())
-    }
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParseFailure {
+    pub field: String,
+    pub raw_text: String,
+    pub reason: ParseFailureReason,
 }
 
-impl SafeParse<String> for ElementRef<'_> {
-    fn safe_parse(&self) -> Option<String> {
-        self.text().next().map(|s| s.to_owned())
+impl ParseFailure {
+    pub fn new(field: &str, raw_text: &str, reason: ParseFailureReason) -> Self {
+        Self {
+            field: field.to_owned(),
+            raw_text: raw_text.to_owned(),
+            reason,
+        }
+    }
+
+    // placeholders are expected (e.g. no trades yet) and aren't real failures
+    pub fn is_no_value(&self) -> bool {
+        self.reason == ParseFailureReason::NoValue
     }
 }
 
-impl SafeParse<NaiveDateTime> for ElementRef<'_> {
-    fn safe_parse(&self) -> Option<NaiveDateTime> {
-        self.text()
-            .next()
-            .and_then(|text| parse_datetime(text).ok())
+impl Display for ParseFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Failed to parse {} from {:?}: {}",
+            self.field, self.raw_text, self.reason
+        )
     }
 }
 
-impl SafeParse<u64> for ElementRef<'_> {
-    fn safe_parse(&self) -> Option<u64> {
-        self.text().next().and_then(|text| parse_int(text).ok())
+pub trait TryParse<T> {
+    fn try_parse(&self, field: &str) -> Result<T, ParseFailure>;
+
+    // like try_parse, but a value that parsed in part is kept and the failures of
+    // the rest are pushed to `failures`
+    fn try_parse_partial(
+        &self,
+        field: &str,
+        _failures: &mut Vec<ParseFailure>,
+    ) -> Result<T, ParseFailure> {
+        self.try_parse(field)
     }
 }
 
-impl SafeParse<PriceDateReference> for ElementRef<'_> {
-    fn safe_parse(&self) -> Option<PriceDateReference> {
-        let price_date_str: String = self.default_parse();
+// where a property value is read from, the first text node of its element
+pub trait ValueText<'a> {
+    fn first_text(&self) -> Option<&'a str>;
 
-        price_date_str.split_once(" - ").map(|tuple| {
-            let price = parse_float(tuple.0).ok();
-            let date = parse_date(tuple.1).ok();
+    // all the text nodes of the element, whitespace collapsed
+    fn joined_text(&self) -> Option<String>;
+}
 
-            PriceDateReference { price, date }
-        })
+impl<'a> ValueText<'a> for ElementRef<'a> {
+    fn first_text(&self) -> Option<&'a str> {
+        self.text().next()
+    }
+
+    fn joined_text(&self) -> Option<String> {
+        Some(collapse_whitespace(self.text()))
     }
 }
 
-impl SafeParse<PriceDateTimeReference> for ElementRef<'_> {
-    fn safe_parse(&self) -> Option<PriceDateTimeReference> {
-        let price_datetime_str: String = self.default_parse();
+pub(crate) fn collapse_whitespace<'t>(texts: impl Iterator<Item = &'t str>) -> String {
+    texts
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
 
-        price_datetime_str.split_once("-").map(|tuple| {
-            let price = parse_float(tuple.0.trim()).ok();
-            let datetime = parse_datetime(tuple.1.trim()).ok();
+// first text node of the element, rejecting placeholders
+pub fn element_text<'a>(
+    element: &impl ValueText<'a>,
+    field: &str,
+) -> Result<&'a str, ParseFailure> {
+    let text = element
+        .first_text()
+        .ok_or_else(|| ParseFailure::new(field, "", ParseFailureReason::MissingText))?;
 
-            PriceDateTimeReference { price, datetime }
+    if NO_VALUE_PLACEHOLDERS.contains(&text.trim().to_lowercase().as_str()) {
+        return Err(ParseFailure::new(field, text, ParseFailureReason::NoValue));
+    }
+
+    Ok(text)
+}
+
+impl<'a, V: ValueText<'a>> TryParse<f64> for V {
+    fn try_parse(&self, field: &str) -> Result<f64, ParseFailure> {
+        let text = element_text(self, field)?;
+        parse_float(text).map_err(|e| {
+            ParseFailure::new(
+                field,
+                text,
+                ParseFailureReason::InvalidNumber(e.to_string()),
+            )
+        })
+    }
+}
+
+impl<'a, V: ValueText<'a>> TryParse<Decimal> for V {
+    fn try_parse(&self, field: &str) -> Result<Decimal, ParseFailure> {
+        let text = element_text(self, field)?;
+        parse_decimal(text).map_err(|e| {
+            ParseFailure::new(
+                field,
+                text,
+                ParseFailureReason::InvalidNumber(e.to_string()),
+            )
         })
     }
 }
 
-impl DefaultParse<f64> for ElementRef<'_> {
-    fn default_parse(&self) -> f64 {
-        self.safe_parse().unwrap_or_default()
+impl<'a, V: ValueText<'a>> TryParse<String> for V {
+    fn try_parse(&self, field: &str) -> Result<String, ParseFailure> {
+        element_text(self, field).map(|text| text.to_owned())
+    }
+}
+
+// for texts split by <br>, <p> or inline tags, e.g. descriptions; as a parser of
+// #[scrape(parser = ...)]
+pub fn parse_joined_text<'a>(
+    element: &impl ValueText<'a>,
+    field: &str,
+) -> Result<String, ParseFailure> {
+    let text = element
+        .joined_text()
+        .ok_or_else(|| ParseFailure::new(field, "", ParseFailureReason::MissingText))?;
+
+    if NO_VALUE_PLACEHOLDERS.contains(&text.to_lowercase().as_str()) {
+        return Err(ParseFailure::new(field, &text, ParseFailureReason::NoValue));
     }
+
+    Ok(text)
 }
 
-impl DefaultParse<String> for ElementRef<'_> {
-    fn default_parse(&self) -> String {
-        self.text().next().unwrap_or("N/A").to_owned()
+impl<'a, V: ValueText<'a>> TryParse<DateTime<Utc>> for V {
+    fn try_parse(&self, field: &str) -> Result<DateTime<Utc>, ParseFailure> {
+        let text = element_text(self, field)?;
+        parse_datetime(text).map_err(|reason| ParseFailure::new(field, text, reason))
     }
 }
 
-impl DefaultParse<NaiveDateTime> for ElementRef<'_> {
-    fn default_parse(&self) -> NaiveDateTime {
-        self.safe_parse().unwrap_or_default()
+impl<'a, V: ValueText<'a>> TryParse<NaiveDate> for V {
+    fn try_parse(&self, field: &str) -> Result<NaiveDate, ParseFailure> {
+        let text = element_text(self, field)?;
+        parse_date(text.trim()).map_err(|e| {
+            ParseFailure::new(field, text, ParseFailureReason::InvalidDate(e.to_string()))
+        })
     }
 }
 
-impl DefaultParse<u64> for ElementRef<'_> {
-    fn default_parse(&self) -> u64 {
-        self.safe_parse().unwrap_or_default()
+impl<'a, V: ValueText<'a>> TryParse<u64> for V {
+    fn try_parse(&self, field: &str) -> Result<u64, ParseFailure> {
+        let text = element_text(self, field)?;
+        parse_int(text).map_err(|e| {
+            ParseFailure::new(
+                field,
+                text,
+                ParseFailureReason::InvalidNumber(e.to_string()),
+            )
+        })
     }
 }
 
-impl DefaultParse<PriceDateReference> for ElementRef<'_> {
-    fn default_parse(&self) -> PriceDateReference {
-        self.safe_parse().unwrap_or_default()
+// a bad or missing date keeps the price, a page showing "6,868 - " has no
+// date yet
+impl<'a, V: ValueText<'a>> TryParse<PriceDateReference> for V {
+    fn try_parse(&self, field: &str) -> Result<PriceDateReference, ParseFailure> {
+        let (reference, date_failure) = parse_price_date(self, field)?;
+        date_failure.map_or(Ok(reference), Err)
+    }
+
+    fn try_parse_partial(
+        &self,
+        field: &str,
+        failures: &mut Vec<ParseFailure>,
+    ) -> Result<PriceDateReference, ParseFailure> {
+        let (reference, date_failure) = parse_price_date(self, field)?;
+        failures.extend(date_failure.inspect(|failure| warn!("{}", failure)));
+        Ok(reference)
     }
 }
 
-impl DefaultParse<PriceDateTimeReference> for ElementRef<'_> {
-    fn default_parse(&self) -> PriceDateTimeReference {
-        self.safe_parse().unwrap_or_default()
+impl<'a, V: ValueText<'a>> TryParse<PriceDateTimeReference> for V {
+    fn try_parse(&self, field: &str) -> Result<PriceDateTimeReference, ParseFailure> {
+        let (reference, datetime_failure) = parse_price_datetime(self, field)?;
+        datetime_failure.map_or(Ok(reference), Err)
+    }
+
+    fn try_parse_partial(
+        &self,
+        field: &str,
+        failures: &mut Vec<ParseFailure>,
+    ) -> Result<PriceDateTimeReference, ParseFailure> {
+        let (reference, datetime_failure) = parse_price_datetime(self, field)?;
+        failures.extend(datetime_failure.inspect(|failure| warn!("{}", failure)));
+        Ok(reference)
     }
 }
 
-fn parse_int(str: &str) -> Result<u64, ParseIntError> {
-    str.trim().replace(".", "").parse()
+// 1,234 - 29/11/24
+fn parse_price_date<'a>(
+    value: &impl ValueText<'a>,
+    field: &str,
+) -> Result<(PriceDateReference, Option<ParseFailure>), ParseFailure> {
+    let (text, price, date) = split_reference(value, field)?;
+    let (date, failure) = parse_reference_date(field, text, date, |date| {
+        parse_date(date).map_err(|e| ParseFailureReason::InvalidDate(e.to_string()))
+    });
+    Ok((PriceDateReference { price, date }, failure))
 }
 
-fn parse_float(text: &str) -> Result<f64, ParseFloatError> {
-    let cleaned = text
-        .trim()
-        .trim_start_matches("+")
-        .trim_end_matches("%")
-        .trim();
+// 1,234 - 29/11/24 17.35.00
+fn parse_price_datetime<'a>(
+    value: &impl ValueText<'a>,
+    field: &str,
+) -> Result<(PriceDateTimeReference, Option<ParseFailure>), ParseFailure> {
+    let (text, price, datetime) = split_reference(value, field)?;
+    let (datetime, failure) = parse_reference_date(field, text, datetime, parse_datetime);
+    Ok((PriceDateTimeReference { price, datetime }, failure))
+}
 
-    let is_negative = cleaned.starts_with("-");
+// the text, its price and the rest, the price is required and a text without
+// "-" has no date
+fn split_reference<'a>(
+    value: &impl ValueText<'a>,
+    field: &str,
+) -> Result<(&'a str, Option<Decimal>, &'a str), ParseFailure> {
+    let text = element_text(value, field)?;
+    let failure = |reason| ParseFailure::new(field, text, reason);
 
-    let cleaned = cleaned.trim_start_matches("-");
+    let (price, date) = text.split_once('-').unwrap_or((text, ""));
+    let price = parse_decimal(price.trim())
+        .map_err(|e| failure(ParseFailureReason::InvalidNumber(e.to_string())))?;
 
-    let normalized = if DOTS_AS_THOUSANDS_SEPARATOR_REGEX.is_match(cleaned) {
-        cleaned.replace(".", "").replace(",", ".")
-    } else {
-        cleaned.replace(",", "")
-    };
+    Ok((text, Some(price), date.trim()))
+}
 
-    normalized
-        .parse()
-        .map(|val: f64| if is_negative { -val } else { val })
+// an empty date isn't a failure, the price comes before its date
+fn parse_reference_date<D>(
+    field: &str,
+    text: &str,
+    date: &str,
+    parse: impl Fn(&str) -> Result<D, ParseFailureReason>,
+) -> (Option<D>, Option<ParseFailure>) {
+    if date.is_empty() {
+        debug!("{} has no date in {:?}", field, text);
+        return (None, None);
+    }
+    match parse(date) {
+        Ok(date) => (Some(date), None),
+        Err(reason) => (None, Some(ParseFailure::new(field, text, reason))),
+    }
+}
+
+fn parse_int(text: &str) -> Result<u64, NumberError> {
+    let value = parse_decimal(text)?;
+    if !value.fract().is_zero() {
+        return Err(NumberError::NotUnsignedInteger);
+    }
+    value.to_u64().ok_or(NumberError::NotUnsignedInteger)
+}
+
+fn parse_float(text: &str) -> Result<f64, NumberError> {
+    parse_decimal(text)?.to_f64().ok_or(NumberError::Overflow)
 }
 
-fn parse_datetime(str: &str) -> Result<NaiveDateTime, chrono::ParseError> {
+fn parse_decimal(text: &str) -> Result<Decimal, NumberError> {
+    let parsed = parse_number(text)?;
+    if let Some(ambiguity) = parsed.ambiguity {
+        debug!(
+            "Ambiguous number {:?} ({} or {}), using {}",
+            text, ambiguity.as_thousands, ambiguity.as_decimal, parsed.value
+        );
+    }
+    Ok(parsed.value)
+}
+
+// the site shows Italian wall-clock times, so they are interpreted
+// in Europe/Rome and converted to UTC
+fn parse_datetime(str: &str) -> Result<DateTime<Utc>, ParseFailureReason> {
     // 29/11/24 16.07.46
     let fmt1 = "%d/%m/%y %H.%M.%S";
     // 29/11/24 - 16.07.46
     let fmt2 = "%d/%m/%y - %H.%M.%S";
 
-    if let Ok(res) = NaiveDateTime::parse_from_str(str, fmt1) {
-        return Ok(res);
-    };
-
     // if first is invalid uses second format
-    NaiveDateTime::parse_from_str(str, fmt2)
+    let naive = NaiveDateTime::parse_from_str(str, fmt1)
+        .or_else(|_| NaiveDateTime::parse_from_str(str, fmt2))
+        .map_err(|e| ParseFailureReason::InvalidDate(e.to_string()))?;
+
+    rome_to_utc(naive).ok_or_else(|| {
+        ParseFailureReason::InvalidDate(format!("{naive} doesn't exist in Europe/Rome"))
+    })
+}
+
+// during the DST fall-back hour the earliest instant is used,
+// times skipped by the spring-forward gap don't exist
+pub(crate) fn rome_to_utc(naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+    naive
+        .and_local_timezone(Rome)
+        .earliest()
+        .map(|datetime| datetime.with_timezone(&Utc))
+}
+
+pub(crate) fn parse_date(str: &str) -> Result<NaiveDate, chrono::ParseError> {
+    // 29/11/24
+    let fmt1 = "%d/%m/%y";
+    // 29/11/2024
+    let fmt2 = "%d/%m/%Y";
+
+    NaiveDate::parse_from_str(str, fmt1).or_else(|_| NaiveDate::parse_from_str(str, fmt2))
 }
 
-fn parse_date(str: &str) -> Result<NaiveDate, chrono::ParseError> {
-    let fmt = "%d/%m/%y";
-    NaiveDate::parse_from_str(str, fmt)
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use crate::shares::property_selector::PropertyValue;
+
+    use super::*;
+
+    const FIELD: &str = "prezzo";
+
+    // parses `text` as the type named by `kind`, keeping only the failure
+    fn parse_failure(kind: &str, text: Option<&str>) -> Option<ParseFailure> {
+        let value = PropertyValue::Text(text);
+        match kind {
+            "f64" => TryParse::<f64>::try_parse(&value, FIELD).err(),
+            "Decimal" => TryParse::<Decimal>::try_parse(&value, FIELD).err(),
+            "String" => TryParse::<String>::try_parse(&value, FIELD).err(),
+            "DateTime" => TryParse::<DateTime<Utc>>::try_parse(&value, FIELD).err(),
+            "NaiveDate" => TryParse::<NaiveDate>::try_parse(&value, FIELD).err(),
+            "u64" => TryParse::<u64>::try_parse(&value, FIELD).err(),
+            "PriceDateReference" => TryParse::<PriceDateReference>::try_parse(&value, FIELD).err(),
+            "PriceDateTimeReference" => {
+                TryParse::<PriceDateTimeReference>::try_parse(&value, FIELD).err()
+            }
+            _ => unreachable!("{}", kind),
+        }
+    }
+
+    fn number(e: NumberError) -> ParseFailureReason {
+        ParseFailureReason::InvalidNumber(e.to_string())
+    }
+
+    fn date(e: &str) -> ParseFailureReason {
+        ParseFailureReason::InvalidDate(e.to_owned())
+    }
+
+    const KINDS: [&str; 8] = [
+        "f64",
+        "Decimal",
+        "String",
+        "DateTime",
+        "NaiveDate",
+        "u64",
+        "PriceDateReference",
+        "PriceDateTimeReference",
+    ];
+
+    #[test]
+    fn rejects_missing_texts_and_placeholders() {
+        for kind in KINDS {
+            assert_eq!(
+                parse_failure(kind, None),
+                Some(ParseFailure::new(
+                    FIELD,
+                    "",
+                    ParseFailureReason::MissingText
+                )),
+                "{}",
+                kind
+            );
+
+            for placeholder in ["", " - ", "--", "N.D.", "n.d."] {
+                assert_eq!(
+                    parse_failure(kind, Some(placeholder)),
+                    Some(ParseFailure::new(
+                        FIELD,
+                        placeholder,
+                        ParseFailureReason::NoValue
+                    )),
+                    "{} {:?}",
+                    kind,
+                    placeholder
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_texts() {
+        let cases = [
+            ("f64", "abc", number(NumberError::InvalidCharacter('a'))),
+            (
+                "f64",
+                "1,2,3",
+                number(NumberError::InvalidGrouping("1,2,3".to_owned())),
+            ),
+            ("Decimal", "12x", number(NumberError::InvalidCharacter('x'))),
+            ("Decimal", "%", number(NumberError::Empty)),
+            ("u64", "1,5", number(NumberError::NotUnsignedInteger)),
+            ("u64", "-3", number(NumberError::NotUnsignedInteger)),
+            ("u64", "abc", number(NumberError::InvalidCharacter('a'))),
+            ("NaiveDate", "31/02/24", date("input is out of range")),
+            (
+                "NaiveDate",
+                "29-11-24",
+                date("input contains invalid characters"),
+            ),
+            ("NaiveDate", "29/11", date("premature end of input")),
+            ("DateTime", "29/11/24", date("premature end of input")),
+            // the error of the second format is reported
+            (
+                "DateTime",
+                "29/11/24 25.00.00",
+                date("input contains invalid characters"),
+            ),
+            (
+                "DateTime",
+                "29/11/24 16:07:46",
+                date("input contains invalid characters"),
+            ),
+            (
+                "DateTime",
+                "31/03/24 02.30.00",
+                date("2024-03-31 02:30:00 doesn't exist in Europe/Rome"),
+            ),
+            (
+                "PriceDateReference",
+                "1,234 29/11/24",
+                number(NumberError::InvalidCharacter('/')),
+            ),
+            (
+                "PriceDateReference",
+                "abc - 29/11/24",
+                number(NumberError::InvalidCharacter('a')),
+            ),
+            (
+                "PriceDateReference",
+                "1,234 - 29.11.24",
+                date("input contains invalid characters"),
+            ),
+            (
+                "PriceDateTimeReference",
+                "1,234 29/11/24 17.35.00",
+                number(NumberError::InvalidCharacter('/')),
+            ),
+            (
+                "PriceDateTimeReference",
+                "1x - 29/11/24 17.35.00",
+                number(NumberError::InvalidCharacter('x')),
+            ),
+            (
+                "PriceDateTimeReference",
+                "1,234 - 29/11/24",
+                date("premature end of input"),
+            ),
+        ];
+
+        for (kind, text, reason) in cases {
+            assert_eq!(
+                parse_failure(kind, Some(text)),
+                Some(ParseFailure::new(FIELD, text, reason)),
+                "{} {:?}",
+                kind,
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn parses_valid_texts() {
+        let value = |text| PropertyValue::Text(Some(text));
+        let datetime = Utc.with_ymd_and_hms(2024, 11, 29, 16, 35, 0).unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 11, 29).unwrap();
+
+        let price: f64 = value("1.234,5").try_parse(FIELD).unwrap();
+        assert_eq!(price, 1234.5);
+        let price: Decimal = value("0,1235").try_parse(FIELD).unwrap();
+        assert_eq!(price, Decimal::new(1235, 4));
+        let text: String = value(" Asta ").try_parse(FIELD).unwrap();
+        assert_eq!(text, " Asta ");
+        let parsed: DateTime<Utc> = value("29/11/24 17.35.00").try_parse(FIELD).unwrap();
+        assert_eq!(parsed, datetime);
+        let parsed: DateTime<Utc> = value("29/11/24 - 17.35.00").try_parse(FIELD).unwrap();
+        assert_eq!(parsed, datetime);
+        let parsed: NaiveDate = value(" 29/11/2024 ").try_parse(FIELD).unwrap();
+        assert_eq!(parsed, day);
+        let volume: u64 = value("1.250").try_parse(FIELD).unwrap();
+        assert_eq!(volume, 1250);
+
+        let reference: PriceDateReference = value("1,234 - 29/11/24").try_parse(FIELD).unwrap();
+        assert_eq!(reference.price, Some(Decimal::new(1234, 3)));
+        assert_eq!(reference.date, Some(day));
+        let reference: PriceDateTimeReference =
+            value("1,234 - 29/11/24 17.35.00").try_parse(FIELD).unwrap();
+        assert_eq!(reference.price, Some(Decimal::new(1234, 3)));
+        assert_eq!(reference.datetime, Some(datetime));
+    }
+
+    #[test]
+    fn keeps_the_price_of_references_without_a_valid_date() {
+        let value = |text| PropertyValue::Text(Some(text));
+        let price = Some(Decimal::new(6868, 3));
+
+        // no date yet, not a failure
+        let mut failures = Vec::new();
+        let reference: PriceDateReference = value("6,868 - ")
+            .try_parse_partial(FIELD, &mut failures)
+            .unwrap();
+        assert_eq!((reference.price, reference.date), (price, None));
+        let reference: PriceDateTimeReference = value("6,868 -")
+            .try_parse_partial(FIELD, &mut failures)
+            .unwrap();
+        assert_eq!((reference.price, reference.datetime), (price, None));
+        let reference: PriceDateReference = value("6,868")
+            .try_parse_partial(FIELD, &mut failures)
+            .unwrap();
+        assert_eq!((reference.price, reference.date), (price, None));
+        assert_eq!(failures, []);
+
+        let reference: PriceDateReference = value("6,868 - 31/02/24")
+            .try_parse_partial(FIELD, &mut failures)
+            .unwrap();
+        assert_eq!((reference.price, reference.date), (price, None));
+        let reference: PriceDateTimeReference = value("6,868 - 29/11/24 17:35")
+            .try_parse_partial(FIELD, &mut failures)
+            .unwrap();
+        assert_eq!((reference.price, reference.datetime), (price, None));
+        assert_eq!(
+            failures,
+            [
+                ParseFailure::new(FIELD, "6,868 - 31/02/24", date("input is out of range")),
+                ParseFailure::new(
+                    FIELD,
+                    "6,868 - 29/11/24 17:35",
+                    date("input contains invalid characters")
+                ),
+            ]
+        );
+
+        // without a price the field fails
+        let failure = TryParse::<PriceDateReference>::try_parse_partial(
+            &value("- 29/11/24"),
+            FIELD,
+            &mut failures,
+        )
+        .unwrap_err();
+        assert_eq!(failure.reason, number(NumberError::Empty));
+        assert_eq!(failures.len(), 2);
+    }
+
+    fn rome(datetime: &str) -> Option<DateTime<Utc>> {
+        rome_to_utc(NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M:%S").unwrap())
+    }
+
+    fn utc(datetime: &str) -> Option<DateTime<Utc>> {
+        Some(
+            NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M:%S")
+                .unwrap()
+                .and_utc(),
+        )
+    }
+
+    #[test]
+    fn skips_the_spring_forward_gap() {
+        // last Sunday of March, 02:00 CET becomes 03:00 CEST
+        assert_eq!(rome("2024-03-31 01:59:59"), utc("2024-03-31 00:59:59"));
+        assert_eq!(rome("2024-03-31 02:00:00"), None);
+        assert_eq!(rome("2024-03-31 02:30:00"), None);
+        assert_eq!(rome("2024-03-31 02:59:59"), None);
+        assert_eq!(rome("2024-03-31 03:00:00"), utc("2024-03-31 01:00:00"));
+    }
+
+    #[test]
+    fn uses_the_earliest_fall_back_instant() {
+        // last Sunday of October, 03:00 CEST becomes 02:00 CET
+        assert_eq!(rome("2024-10-27 01:59:59"), utc("2024-10-26 23:59:59"));
+        assert_eq!(rome("2024-10-27 02:00:00"), utc("2024-10-27 00:00:00"));
+        assert_eq!(rome("2024-10-27 02:30:00"), utc("2024-10-27 00:30:00"));
+        assert_eq!(rome("2024-10-27 03:00:00"), utc("2024-10-27 02:00:00"));
+    }
+
+    #[test]
+    fn keeps_the_order_across_switches() {
+        for day in ["2024-03-31", "2024-10-27", "2025-03-30", "2025-10-26"] {
+            let instants: Vec<DateTime<Utc>> = (0..24 * 4)
+                .filter_map(|quarter| {
+                    let time = format!("{} {:02}:{:02}:00", day, quarter / 4, quarter % 4 * 15);
+                    rome(&time)
+                })
+                .collect();
+
+            // the gap drops four quarters, the repeated hour is read once
+            let expected = if day.contains("-03-") { 92 } else { 96 };
+            assert_eq!(instants.len(), expected, "{}", day);
+            assert!(instants.windows(2).all(|pair| pair[0] < pair[1]), "{}", day);
+        }
+    }
 }
@@ -0,0 +1,109 @@
+use futures::{stream::FuturesUnordered, StreamExt};
+use scraper::Html;
+use tracing::{debug, info, info_span, warn, Instrument};
+
+use super::{
+    mappings::field_mappings,
+    models::{company_profile::CompanyProfile, ScrapableStruct},
+    parsers::ParseFailure,
+    property_selector::PropertySelector,
+};
+use crate::{
+    errors::ScraperResult,
+    get_page_text,
+    isins::types::ShareIsin,
+    metrics::{ScrapingMetrics, WithMetrics},
+};
+
+pub async fn scrape_all_profiles(share_isins: Vec<ShareIsin>) -> WithMetrics<Vec<CompanyProfile>> {
+    let mut metrics = ScrapingMetrics::empty();
+    metrics.total = share_isins.len() as i32;
+    let mut tasks = FuturesUnordered::new();
+
+    for share_isin in share_isins.iter() {
+        let isin_str = share_isin.isin.to_string();
+        tasks.push(
+            scrape_profile(share_isin).instrument(info_span!("scraping_profile", isin = isin_str)),
+        );
+    }
+
+    let mut res: Vec<CompanyProfile> = Vec::new();
+    while let Some(result) = tasks.next().await {
+        match result {
+            Ok((profile, failures)) => {
+                metrics.successful += 1;
+                metrics.errors.field_parsing_error += failures.len() as i32;
+                res.push(profile);
+            }
+            Err(e) => metrics.errors.update(e),
+        }
+    }
+    info!("Scraped a total of {} company profiles.", res.len());
+
+    WithMetrics::new(res, metrics)
+}
+
+pub async fn scrape_profile(
+    share_isin: &ShareIsin,
+) -> ScraperResult<(CompanyProfile, Vec<ParseFailure>)> {
+    let url = format!(
+        "https://www.borsaitaliana.it/borsa/azioni/profilo-societa.html?isin={}&lang=it",
+        share_isin.isin
+    );
+
+    let res_txt = get_page_text(url)
+        .instrument(info_span!("fetching_page"))
+        .await?;
+
+    Ok(parse_profile_page(&res_txt, share_isin))
+}
+
+pub fn parse_profile_page(
+    res_txt: &str,
+    share_isin: &ShareIsin,
+) -> (CompanyProfile, Vec<ParseFailure>) {
+    debug!("Parsing company profile page");
+
+    let doc = Html::parse_document(res_txt);
+    let selector = PropertySelector::with_mappings(&doc, &field_mappings().profile);
+    let mut failures = Vec::new();
+    let profile = CompanyProfile::from_selector(share_isin, &selector, &mut failures);
+    if !failures.is_empty() {
+        warn!("{} profile fields failed to parse", failures.len());
+    }
+
+    (profile, failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<table>
+      <tr><td><strong>Ragione sociale</strong></td>
+          <td><span class="t-text -right">Enel S.p.A.</span></td></tr>
+      <tr><td><strong>Descrizione</strong></td>
+          <td><span class="t-text -right">Enel &egrave; una <b>multinazionale</b>
+            dell'energia.<br>Opera in 28 paesi,
+            <p>con oltre 60 GW di capacit&agrave; installata.</p></span></td></tr>
+      <tr><td><strong>Sito web</strong></td>
+          <td><span class="t-text -right"><a href="https://www.enel.com">www.enel.com</a></span></td></tr>
+    </table>"#;
+
+    #[test]
+    fn joins_the_text_of_descriptions() {
+        let share_isin = ShareIsin::new("ENEL".to_owned(), "IT0003128367".to_owned()).unwrap();
+        let (profile, failures) = parse_profile_page(PAGE, &share_isin);
+
+        assert!(failures.is_empty(), "{:?}", failures);
+        assert_eq!(profile.ragione_sociale.as_deref(), Some("Enel S.p.A."));
+        assert_eq!(
+            profile.descrizione.as_deref(),
+            Some(
+                "Enel è una multinazionale dell'energia. Opera in 28 paesi, \
+                 con oltre 60 GW di capacità installata."
+            )
+        );
+        assert_eq!(profile.sito_web.as_deref(), Some("www.enel.com"));
+    }
+}
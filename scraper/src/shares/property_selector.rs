@@ -3,101 +3,175 @@ use scraper::{ElementRef, Html, Selector};
 use std::collections::HashMap;
 use tracing::warn;
 
-static TABLE_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("table").unwrap());
-static ROW_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("tr").unwrap());
+#[cfg(feature = "streaming")]
+use super::streaming::{StreamedPage, StreamedRow};
+use super::{
+    mappings::{field_mappings, FieldMapping, MatchMode},
+    parsers::{collapse_whitespace, ValueText},
+    OrderBook,
+};
+use crate::isins::types::ShareIsin;
+
+pub(crate) const DEFAULT_VALUE_SELECTOR: &str = "span.t-text.-right";
+
+static LABEL_ROW_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("table tr").unwrap());
 static STRONG_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("strong").unwrap());
 static VALUE_SELECTOR: Lazy<Selector> =
-    Lazy::new(|| Selector::parse("span.t-text.-right").unwrap());
-
-static MAPPINGS: Lazy<Vec<(&'static str, Vec<&'static str>)>> = Lazy::new(|| {
-    vec![
-        ("id_strumento", vec!["id strumento"]),
-        ("codice_alfanumerico", vec!["codice alfanumerico"]),
-        ("super_sector", vec!["super sector"]),
-        ("mercato_segmento", vec!["mercato/segmento"]),
-        (
-            "capitalizzazione_di_mercato",
-            vec!["capitalizzazione di mercato"],
-        ),
-        ("lotto_minimo", vec!["lotto minimo"]),
-        ("fase_di_mercato", vec!["fase di mercato"]),
-        ("prezzo_ultimo_contratto", vec!["prezzo ultimo contratto"]),
-        ("var_percentuale", vec!["var %"]),
-        ("var_assoluta", vec!["var assoluta"]),
-        ("pr_medio_progr", vec!["pr medio progr"]),
-        (
-            "data_ora_ultimo_contratto",
-            vec!["data - ora ultimo contratto:"],
-        ),
-        ("quantita_ultimo", vec!["quantità ultimo"]),
-        ("quantita_totale", vec!["quantità totale"]),
-        ("numero_contratti", vec!["numero contratti"]),
-        ("controvalore", vec!["controvalore"]),
-        ("max_oggi", vec!["max oggi"]),
-        ("max_anno", vec!["max anno"]),
-        ("min_oggi", vec!["min oggi"]),
-        ("min_anno", vec!["min anno"]),
-        (
-            "chiusura_precedente",
-            vec!["chiusura precedente/pre-chiusura/chiusura:"],
-        ),
-        ("prezzo_riferimento", vec!["prezzo di riferimento"]),
-        ("prezzo_ufficiale", vec!["prezzo ufficiale"]),
-        ("apertura_odierna", vec!["apertura odierna:"]),
-        ("performance_1_mese", vec!["performance 1 mese"]),
-        ("performance_6_mesi", vec!["performance 6 mesi"]),
-        ("performance_1_anno", vec!["performance 1 anno"]),
-    ]
-});
+    Lazy::new(|| Selector::parse(DEFAULT_VALUE_SELECTOR).unwrap());
+
+// the element of a property, or its text when the page was streamed
+#[derive(Debug, Clone, Copy)]
+pub enum PropertyValue<'a> {
+    Element(ElementRef<'a>),
+    Text(Option<&'a str>),
+}
+
+impl<'a> ValueText<'a> for PropertyValue<'a> {
+    fn first_text(&self) -> Option<&'a str> {
+        match self {
+            PropertyValue::Element(element) => element.first_text(),
+            PropertyValue::Text(text) => *text,
+        }
+    }
+
+    fn joined_text(&self) -> Option<String> {
+        match self {
+            PropertyValue::Element(element) => element.joined_text(),
+            PropertyValue::Text(text) => text.map(|text| collapse_whitespace([text].into_iter())),
+        }
+    }
+}
+
+enum Page<'a> {
+    Document(&'a Html),
+    #[cfg(feature = "streaming")]
+    Streamed(&'a StreamedPage),
+}
+
+#[derive(Clone, Copy)]
+enum LabelRow<'a> {
+    Element(ElementRef<'a>),
+    #[cfg(feature = "streaming")]
+    Streamed(&'a StreamedRow),
+}
 
 pub struct PropertySelector<'a> {
-    index: HashMap<String, ElementRef<'a>>,
-    prop_mapping: HashMap<&'static str, String>,
+    page: Page<'a>,
+    prop_mapping: HashMap<&'a str, (LabelRow<'a>, &'a FieldMapping)>,
 }
 
 impl<'a> PropertySelector<'a> {
     pub fn new(document: &'a Html) -> Self {
-        let mut index = HashMap::new();
-        let mut prop_mapping = HashMap::new();
-
-        for (rust_prop, search_terms) in MAPPINGS.iter() {
-            for table in document.select(&TABLE_SELECTOR) {
-                for row in table.select(&ROW_SELECTOR) {
-                    if let Some(strong_elem) = row.select(&STRONG_SELECTOR).next() {
-                        let text = strong_elem.text().collect::<String>().to_lowercase();
-
-                        if search_terms.iter().any(|term| text.contains(term)) {
-                            index.insert(text.clone(), row);
-                            prop_mapping.insert(*rust_prop, text.clone());
-                        }
-                    }
-                }
-            }
+        Self::with_mappings(document, &field_mappings().share)
+    }
+
+    pub fn with_mappings(document: &'a Html, mappings: &'a [FieldMapping]) -> Self {
+        let labels = document
+            .select(&LABEL_ROW_SELECTOR)
+            .filter_map(|row| {
+                let strong_elem = row.select(&STRONG_SELECTOR).next()?;
+                Some((
+                    strong_elem.text().collect::<String>().to_lowercase(),
+                    LabelRow::Element(row),
+                ))
+            })
+            .collect();
+
+        Self {
+            page: Page::Document(document),
+            prop_mapping: resolve_mappings(labels, mappings),
         }
+    }
+
+    // `page` must be extracted with the same mappings
+    #[cfg(feature = "streaming")]
+    pub fn streamed(page: &'a StreamedPage, mappings: &'a [FieldMapping]) -> Self {
+        let labels = page
+            .rows
+            .iter()
+            .filter_map(|row| {
+                let label = row.label.as_ref()?;
+                Some((label.to_lowercase(), LabelRow::Streamed(row)))
+            })
+            .collect();
 
         Self {
-            index,
-            prop_mapping,
+            page: Page::Streamed(page),
+            prop_mapping: resolve_mappings(labels, mappings),
         }
     }
 
-    pub fn get_property(&self, prop: &str) -> Option<ElementRef<'a>> {
-        let indexed_text = self.prop_mapping.get(prop)?;
+    pub fn get_property(&self, prop: &str) -> Option<PropertyValue<'a>> {
+        let (row, mapping) = self.prop_mapping.get(prop)?;
 
-        let row = match self.index.get(indexed_text) {
-            Some(row) => row,
-            None => {
-                warn!("No element found for {}", prop);
-                return None;
+        let value = match (row, &self.page) {
+            (LabelRow::Element(row), _) => {
+                let value_selector = mapping
+                    .value_selector
+                    .as_ref()
+                    .map_or(&*VALUE_SELECTOR, |value_selector| &value_selector.selector);
+                row.select(value_selector)
+                    .next()
+                    .map(PropertyValue::Element)
+            }
+            #[cfg(feature = "streaming")]
+            (LabelRow::Streamed(row), Page::Streamed(page)) => {
+                let css = mapping
+                    .value_selector
+                    .as_ref()
+                    .map_or(DEFAULT_VALUE_SELECTOR, |value_selector| &value_selector.css);
+                page.value(row, css).map(PropertyValue::Text)
             }
+            #[cfg(feature = "streaming")]
+            (LabelRow::Streamed(_), Page::Document(_)) => None,
         };
 
-        match row.select(&VALUE_SELECTOR).next() {
-            Some(el) => Some(el),
-            None => {
-                warn!("No element found for {}", prop);
-                None
-            }
+        if value.is_none() {
+            warn!("No element found for {}", prop);
+        }
+        value
+    }
+
+    pub fn order_book(&self, share_isin: &ShareIsin) -> Option<OrderBook> {
+        match self.page {
+            Page::Document(document) => OrderBook::from_document(share_isin, document),
+            #[cfg(feature = "streaming")]
+            Page::Streamed(page) => OrderBook::from_tables(
+                share_isin,
+                page.tables
+                    .iter()
+                    .map(|table| (table.headers.as_slice(), table.rows.as_slice())),
+            ),
         }
     }
 }
+
+// the label rows are collected in a single traversal, then each mapping is
+// resolved against them; when several rows match, the last one wins
+fn resolve_mappings<'a>(
+    labels: Vec<(String, LabelRow<'a>)>,
+    mappings: &'a [FieldMapping],
+) -> HashMap<&'a str, (LabelRow<'a>, &'a FieldMapping)> {
+    let exact_labels: HashMap<&str, LabelRow<'a>> = labels
+        .iter()
+        .map(|(label, row)| (label.trim(), *row))
+        .collect();
+
+    mappings
+        .iter()
+        .filter_map(|mapping| {
+            let row = match mapping.match_mode {
+                MatchMode::Exact => mapping
+                    .terms
+                    .iter()
+                    .find_map(|term| exact_labels.get(term.as_str()).copied()),
+                MatchMode::Contains => labels
+                    .iter()
+                    .rev()
+                    .find(|(label, _)| mapping.matches(label))
+                    .map(|(_, row)| *row),
+            }?;
+            Some((mapping.field.as_str(), (row, mapping)))
+        })
+        .collect()
+}
@@ -0,0 +1,381 @@
+// extracts the label rows and the tables of a share page in a single pass over
+// the html, without building a DOM
+//
+// rows and tables are assumed not to nest (they don't on share pages), content is
+// attributed to the last row/table that was opened
+
+use std::{borrow::Cow, cell::RefCell, fmt::Display};
+
+use lol_html::{
+    errors::{RewritingError, SelectorError},
+    html_content::{Element, TextChunk},
+    ElementContentHandlers, HandlerResult, HtmlRewriter, Selector, Settings,
+};
+
+use super::{mappings::FieldMapping, property_selector::DEFAULT_VALUE_SELECTOR};
+
+#[derive(Debug)]
+pub enum StreamingError {
+    Selector { css: String, error: SelectorError },
+    Rewriting(RewritingError),
+}
+
+impl Display for StreamingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamingError::Selector { css, error } => {
+                write!(f, "unsupported selector {:?}: {}", css, error)
+            }
+            StreamingError::Rewriting(e) => write!(f, "unable to stream the page: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for StreamingError {}
+
+#[derive(Debug, Default)]
+pub struct StreamedPage {
+    // css of the value selectors, `StreamedRow::values` is indexed the same way
+    value_selectors: Vec<String>,
+    pub(crate) rows: Vec<StreamedRow>,
+    pub(crate) tables: Vec<StreamedTable>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct StreamedRow {
+    // text of the first strong element in the row
+    pub(crate) label: Option<String>,
+    // the first element matching each value selector, None if there is none
+    values: Vec<Option<StreamedValue>>,
+}
+
+#[derive(Debug, Default)]
+struct StreamedValue {
+    // first text node of the element
+    text: Option<String>,
+    complete: bool,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct StreamedTable {
+    pub(crate) headers: Vec<String>,
+    // cell texts of each row
+    pub(crate) rows: Vec<Vec<String>>,
+}
+
+// what the text handlers are currently capturing
+#[derive(Default)]
+struct ExtractState {
+    page: StreamedPage,
+    in_label: bool,
+    in_value: Vec<bool>,
+}
+
+impl StreamedPage {
+    // the value selectors are the ones used by `mappings`
+    pub fn extract(html: &str, mappings: &[FieldMapping]) -> Result<Self, StreamingError> {
+        let mut value_selectors = vec![DEFAULT_VALUE_SELECTOR.to_owned()];
+        for mapping in mappings {
+            if let Some(value_selector) = &mapping.value_selector {
+                if !value_selectors.contains(&value_selector.css) {
+                    value_selectors.push(value_selector.css.clone());
+                }
+            }
+        }
+
+        let state = RefCell::new(ExtractState {
+            in_value: vec![false; value_selectors.len()],
+            page: StreamedPage {
+                value_selectors: value_selectors.clone(),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let mut handlers = vec![
+            element_handler("table", |_| {
+                let mut state = state.borrow_mut();
+                state.page.tables.push(StreamedTable::default());
+                Ok(())
+            })?,
+            element_handler("table tr", |_| {
+                let mut state = state.borrow_mut();
+                let values = value_selectors.iter().map(|_| None).collect();
+                state.page.rows.push(StreamedRow {
+                    label: None,
+                    values,
+                });
+                if let Some(table) = state.page.tables.last_mut() {
+                    table.rows.push(Vec::new());
+                }
+                Ok(())
+            })?,
+            element_handler("table tr strong", |_| {
+                let mut state = state.borrow_mut();
+                let row = state.page.rows.last_mut();
+                state.in_label = match row {
+                    Some(row) if row.label.is_none() => {
+                        row.label = Some(String::new());
+                        true
+                    }
+                    _ => false,
+                };
+                Ok(())
+            })?,
+            text_handler("table tr strong", |chunk| {
+                let mut state = state.borrow_mut();
+                if state.in_label {
+                    let label = state
+                        .page
+                        .rows
+                        .last_mut()
+                        .and_then(|row| row.label.as_mut());
+                    if let Some(label) = label {
+                        label.push_str(chunk.as_str());
+                    }
+                }
+                Ok(())
+            })?,
+            element_handler("table th", |_| {
+                let mut state = state.borrow_mut();
+                if let Some(table) = state.page.tables.last_mut() {
+                    table.headers.push(String::new());
+                }
+                Ok(())
+            })?,
+            text_handler("table th", |chunk| {
+                let mut state = state.borrow_mut();
+                let header = state
+                    .page
+                    .tables
+                    .last_mut()
+                    .and_then(|table| table.headers.last_mut());
+                if let Some(header) = header {
+                    header.push_str(chunk.as_str());
+                }
+                Ok(())
+            })?,
+            element_handler("table td", |_| {
+                let mut state = state.borrow_mut();
+                let row = state
+                    .page
+                    .tables
+                    .last_mut()
+                    .and_then(|table| table.rows.last_mut());
+                if let Some(row) = row {
+                    row.push(String::new());
+                }
+                Ok(())
+            })?,
+            text_handler("table td", |chunk| {
+                let mut state = state.borrow_mut();
+                let cell = state
+                    .page
+                    .tables
+                    .last_mut()
+                    .and_then(|table| table.rows.last_mut())
+                    .and_then(|row| row.last_mut());
+                if let Some(cell) = cell {
+                    cell.push_str(chunk.as_str());
+                }
+                Ok(())
+            })?,
+        ];
+
+        for (i, css) in value_selectors.iter().enumerate() {
+            // the values are looked up inside the label rows
+            let css = css
+                .split(',')
+                .map(|part| format!("table tr {}", part.trim()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let state = &state;
+
+            handlers.push(element_handler(&css, move |_| {
+                let mut state = state.borrow_mut();
+                let row = state.page.rows.last_mut();
+                state.in_value[i] = match row {
+                    Some(row) if row.values[i].is_none() => {
+                        row.values[i] = Some(StreamedValue::default());
+                        true
+                    }
+                    _ => false,
+                };
+                Ok(())
+            })?);
+            handlers.push(text_handler(&css, move |chunk| {
+                let mut state = state.borrow_mut();
+                if !state.in_value[i] {
+                    return Ok(());
+                }
+                let value = state
+                    .page
+                    .rows
+                    .last_mut()
+                    .and_then(|row| row.values[i].as_mut());
+                if let Some(value) = value.filter(|value| !value.complete) {
+                    value
+                        .text
+                        .get_or_insert_with(String::new)
+                        .push_str(chunk.as_str());
+                    value.complete = chunk.last_in_text_node();
+                }
+                Ok(())
+            })?);
+        }
+
+        let mut rewriter = HtmlRewriter::new(
+            Settings {
+                element_content_handlers: handlers,
+                ..Settings::new()
+            },
+            |_: &[u8]| {},
+        );
+        rewriter
+            .write(html.as_bytes())
+            .map_err(StreamingError::Rewriting)?;
+        rewriter.end().map_err(StreamingError::Rewriting)?;
+
+        let mut page = state.into_inner().page;
+        page.decode_texts();
+
+        Ok(page)
+    }
+
+    // the value found by `css` in `row`, Some(None) if the element has no text
+    pub(crate) fn value<'a>(&self, row: &'a StreamedRow, css: &str) -> Option<Option<&'a str>> {
+        let i = self.value_selectors.iter().position(|s| s == css)?;
+        let value = row.values.get(i)?.as_ref()?;
+        Some(value.text.as_deref())
+    }
+
+    // text chunks are passed as they appear in the html
+    fn decode_texts(&mut self) {
+        for row in self.rows.iter_mut() {
+            row.label.iter_mut().for_each(decode_text);
+            row.values
+                .iter_mut()
+                .flatten()
+                .filter_map(|value| value.text.as_mut())
+                .for_each(decode_text);
+        }
+        for table in self.tables.iter_mut() {
+            table.headers.iter_mut().for_each(decode_text);
+            table.rows.iter_mut().flatten().for_each(decode_text);
+        }
+    }
+}
+
+// resolves character references and normalizes newlines the way the DOM parser
+// does
+fn decode_text(text: &mut String) {
+    if text.contains('\r') {
+        *text = text.replace("\r\n", "\n").replace('\r', "\n");
+    }
+    if let Cow::Owned(decoded) = html_escape::decode_html_entities(text) {
+        *text = decoded;
+    }
+}
+
+type Handler<'h> = (Cow<'static, Selector>, ElementContentHandlers<'h>);
+
+fn parse_selector(css: &str) -> Result<Selector, StreamingError> {
+    css.parse().map_err(|error| StreamingError::Selector {
+        css: css.to_owned(),
+        error,
+    })
+}
+
+fn element_handler<'h>(
+    css: &str,
+    handler: impl FnMut(&mut Element) -> HandlerResult + 'h,
+) -> Result<Handler<'h>, StreamingError> {
+    Ok((
+        Cow::Owned(parse_selector(css)?),
+        ElementContentHandlers::default().element(handler),
+    ))
+}
+
+fn text_handler<'h>(
+    css: &str,
+    handler: impl FnMut(&mut TextChunk) -> HandlerResult + 'h,
+) -> Result<Handler<'h>, StreamingError> {
+    Ok((
+        Cow::Owned(parse_selector(css)?),
+        ElementContentHandlers::default().text(handler),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use scraper::Html;
+
+    use crate::shares::{
+        mappings::field_mappings, parsers::ValueText, property_selector::PropertySelector,
+        ScrapableStruct, Share,
+    };
+
+    use super::*;
+
+    // unclosed rows and cells, character references and nested text, which
+    // the DOM parser takes care of
+    const PAGE: &str = r#"<table>
+      <tr><td><strong>Prezzo ultimo contratto</strong><td><span class="t-text -right">6,868</span>
+      <tr><td><strong class="t-text">Quantit&agrave; ultimo</strong></td>
+          <td><span class="t-text -right">1.250&nbsp;</span></td></tr>
+      <tr><td><strong>Fase di mercato</strong></td>
+          <td><span class="t-text -right"><b>Asta</b> di chiusura</span></td></tr>
+      <tr><td><strong>Lotto minimo</strong> <strong>ignored</strong></td>
+          <td><span class="t-text -right"></span><span class="t-text -right">1</span></td></tr>
+    </table>
+    <table>
+      <tr><th>N. Ordini</th><th>Quantit&agrave;</th><th>Denaro</th>
+          <th>Lettera</th><th>Quantit&agrave;</th><th>N. Ordini</th></tr>
+      <tr><td>3<td>1.200<td>6,866<td>6,868<td>900<td>2
+    </table>"#;
+
+    #[test]
+    fn streamed_values_match_dom() {
+        let mappings = &field_mappings().share;
+        let document = Html::parse_document(PAGE);
+        let dom = PropertySelector::new(&document);
+        let page = StreamedPage::extract(PAGE, mappings).unwrap();
+        let streamed = PropertySelector::streamed(&page, mappings);
+
+        for field in Share::fields() {
+            let dom_text = dom.get_property(field).map(|value| value.first_text());
+            let streamed_text = streamed.get_property(field).map(|value| value.first_text());
+            assert_eq!(dom_text, streamed_text, "{}", field);
+        }
+
+        let share_isin =
+            crate::isins::types::ShareIsin::new("ENEL".to_owned(), "IT0003128367".to_owned())
+                .unwrap();
+        let dom_book = dom.order_book(&share_isin).unwrap();
+        let streamed_book = streamed.order_book(&share_isin).unwrap();
+        assert_eq!(dom_book.bids, streamed_book.bids);
+        assert_eq!(dom_book.asks, streamed_book.asks);
+        assert_eq!(streamed_book.bids.len(), 1);
+    }
+
+    #[test]
+    fn decodes_texts_like_dom() {
+        let texts = [
+            "Quantit&agrave; &amp; prezzo",
+            "1.250&nbsp;",
+            "&euro; 6,868",
+            "&#8364; &#x20AC;",
+            "&lt;b&gt; &quot;asta&quot; &apos;",
+            "AT&T & co",
+            "&unknown; entity",
+            "line\r\nbreak\rend",
+        ];
+
+        for text in texts {
+            let dom: String = Html::parse_fragment(text).root_element().text().collect();
+            let mut decoded = text.to_owned();
+            decode_text(&mut decoded);
+            assert_eq!(decoded, dom, "{}", text);
+        }
+    }
+}
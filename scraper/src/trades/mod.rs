@@ -0,0 +1,103 @@
+use chrono::Utc;
+use futures::{stream::FuturesUnordered, StreamExt};
+use once_cell::sync::Lazy;
+use scraper::{ElementRef, Html, Selector};
+use tracing::{debug, info, info_span, warn, Instrument};
+use types::{resolve_trading_day, sequence_trades, RawTrade, Trade, TradeColumns};
+
+use crate::{
+    errors::{ScraperResult, ScrapingError},
+    get_page_text,
+    isins::types::ShareIsin,
+    metrics::{ScrapingMetrics, WithMetrics},
+};
+
+pub mod types;
+
+static TABLE_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("table").unwrap());
+static ROW_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("tr").unwrap());
+static HEADER_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("th").unwrap());
+static CELL_SELECTOR: Lazy<Selector> = Lazy::new(|| Selector::parse("td").unwrap());
+
+pub async fn scrape_all_trades(share_isins: Vec<ShareIsin>) -> WithMetrics<Vec<Trade>> {
+    let mut metrics = ScrapingMetrics::empty();
+    metrics.total = share_isins.len() as i32;
+    let mut tasks = FuturesUnordered::new();
+
+    for share_isin in share_isins.iter() {
+        let isin_str = share_isin.isin.to_string();
+        tasks.push(
+            scrape_trades(share_isin).instrument(info_span!("scraping_trades", isin = isin_str)),
+        );
+    }
+
+    let mut res: Vec<Trade> = Vec::new();
+    while let Some(result) = tasks.next().await {
+        match result {
+            Ok(trades) => {
+                metrics.successful += 1;
+                res.extend(trades);
+            }
+            Err(e) => metrics.errors.update(e),
+        }
+    }
+    info!("Scraped a total of {} trades.", res.len());
+
+    WithMetrics::new(res, metrics)
+}
+
+pub async fn scrape_trades(share_isin: &ShareIsin) -> ScraperResult<Vec<Trade>> {
+    let url = format!(
+        "https://www.borsaitaliana.it/borsa/azioni/contratti.html?isin={}&lang=it",
+        share_isin.isin
+    );
+
+    let res_txt = get_page_text(url)
+        .instrument(info_span!("fetching_page"))
+        .await?;
+
+    parse_page(&res_txt, &share_isin.isin.to_string())
+}
+
+pub fn parse_page(res_txt: &str, isin: &str) -> ScraperResult<Vec<Trade>> {
+    debug!("Parsing trades page");
+
+    let doc = Html::parse_document(res_txt);
+    let (table, columns) = doc
+        .select(&TABLE_SELECTOR)
+        .find_map(|table| {
+            let headers: Vec<String> = table
+                .select(&HEADER_SELECTOR)
+                .map(|th| th.text().collect::<String>().trim().to_lowercase())
+                .collect();
+            TradeColumns::from_headers(&headers).map(|columns| (table, columns))
+        })
+        .ok_or(ScrapingError::InvalidPage)?;
+
+    let rows: Vec<Vec<ElementRef>> = table
+        .select(&ROW_SELECTOR)
+        .map(|row| row.select(&CELL_SELECTOR).collect::<Vec<_>>())
+        .filter(|cells| !cells.is_empty())
+        .collect();
+
+    // the newest trade is listed first
+    let latest_time = rows
+        .first()
+        .and_then(|cells| columns.time_text(cells))
+        .and_then(|text| types::parse_time_only(&text));
+    let trading_day = resolve_trading_day(Utc::now(), latest_time);
+
+    let mut raw_trades = Vec::new();
+    for cells in rows.iter().rev() {
+        match RawTrade::from_cells(cells, &columns, trading_day) {
+            Ok(trade) => raw_trades.push(trade),
+            Err(e) => warn!("Invalid trade row: {:?}", e),
+        }
+    }
+    raw_trades.sort_by_key(|trade| trade.traded_at);
+
+    let trades = sequence_trades(isin, raw_trades);
+    debug!("Found {} trades", trades.len());
+
+    Ok(trades)
+}
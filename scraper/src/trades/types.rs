@@ -0,0 +1,346 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc, Weekday};
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use scraper::ElementRef;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::{
+    errors::{ScraperResult, ScrapingError},
+    shares::{numbers::parse_number, parsers::rome_to_utc},
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Trade {
+    pub isin: String,
+    pub traded_at: DateTime<Utc>,
+    pub price: Decimal,
+    #[sqlx(try_from = "i64")]
+    pub quantity: u64,
+    // distinguishes identical trades (same time, price and quantity),
+    // it's the position among them counting from the oldest
+    pub seq: i32,
+}
+
+// column positions of the trade list, found from its header
+pub(super) struct TradeColumns {
+    time: usize,
+    price: usize,
+    quantity: usize,
+}
+
+impl TradeColumns {
+    pub(super) fn from_headers(headers: &[String]) -> Option<Self> {
+        let find = |term: &str| headers.iter().position(|h| h.starts_with(term));
+
+        Some(Self {
+            time: find("ora")?,
+            price: find("prezzo")?,
+            quantity: find("quantit")?,
+        })
+    }
+
+    pub(super) fn time_text(&self, cells: &[ElementRef]) -> Option<String> {
+        cells
+            .get(self.time)
+            .map(|cell| cell.text().collect::<String>().trim().to_owned())
+    }
+}
+
+pub(super) struct RawTrade {
+    pub(super) traded_at: DateTime<Utc>,
+    price: Decimal,
+    quantity: u64,
+}
+
+impl RawTrade {
+    // `trading_day` is used when the page shows only the time
+    pub(super) fn from_cells(
+        cells: &[ElementRef],
+        columns: &TradeColumns,
+        trading_day: NaiveDate,
+    ) -> ScraperResult<Self> {
+        let cell_text = |i: usize| {
+            cells
+                .get(i)
+                .map(|cell| cell.text().collect::<String>().trim().to_owned())
+                .ok_or(ScrapingError::InvalidPage)
+        };
+
+        let traded_at = parse_trade_time(&cell_text(columns.time)?, trading_day)
+            .ok_or(ScrapingError::ParsingErr)?;
+        let price = parse_number(&cell_text(columns.price)?)
+            .map_err(|_| ScrapingError::ParsingErr)?
+            .value;
+        let quantity = parse_number(&cell_text(columns.quantity)?)
+            .ok()
+            .and_then(|n| n.value.to_u64())
+            .ok_or(ScrapingError::ParsingErr)?;
+
+        Ok(Self {
+            traded_at,
+            price,
+            quantity,
+        })
+    }
+}
+
+// numbers the identical trades, the list must be sorted from the oldest
+pub(super) fn sequence_trades(isin: &str, raw_trades: Vec<RawTrade>) -> Vec<Trade> {
+    let mut occurrences: HashMap<(DateTime<Utc>, Decimal, u64), i32> = HashMap::new();
+
+    raw_trades
+        .into_iter()
+        .map(|raw| {
+            let seq = occurrences
+                .entry((raw.traded_at, raw.price, raw.quantity))
+                .or_default();
+            let trade = Trade {
+                isin: isin.to_owned(),
+                traded_at: raw.traded_at,
+                price: raw.price,
+                quantity: raw.quantity,
+                seq: *seq,
+            };
+            *seq += 1;
+            trade
+        })
+        .collect()
+}
+
+fn parse_trade_time(text: &str, trading_day: NaiveDate) -> Option<DateTime<Utc>> {
+    // 29/11/24 17.35.12
+    for fmt in [
+        "%d/%m/%y %H.%M.%S",
+        "%d/%m/%y %H:%M:%S",
+        "%d/%m/%y - %H.%M.%S",
+    ] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(text, fmt) {
+            return rome_to_utc(datetime);
+        }
+    }
+
+    // 17.35.12
+    let time = parse_time_only(text)?;
+    debug!("Trade time {} has no date, using {}", text, trading_day);
+
+    rome_to_utc(trading_day.and_time(time))
+}
+
+// the trade list only shows times, so trades after the current Italian time
+// belong to the previous trading day (e.g. scraping right after midnight), the
+// days the exchange is closed are skipped
+pub(super) fn resolve_trading_day(now: DateTime<Utc>, latest_time: Option<NaiveTime>) -> NaiveDate {
+    let rome_now = now.with_timezone(&chrono_tz::Europe::Rome);
+    let mut day = rome_now.date_naive();
+
+    if matches!(latest_time, Some(time) if time > rome_now.time()) {
+        day -= Duration::days(1);
+    }
+    while !is_trading_day(day) {
+        day -= Duration::days(1);
+    }
+
+    day
+}
+
+// the closing days of the Borsa Italiana calendar, the same every year apart
+// from Good Friday and Easter Monday
+fn is_trading_day(day: NaiveDate) -> bool {
+    if matches!(day.weekday(), Weekday::Sat | Weekday::Sun) {
+        return false;
+    }
+
+    let easter = easter_sunday(day.year());
+    let closed = matches!(
+        (day.month(), day.day()),
+        (1, 1) | (5, 1) | (8, 15) | (12, 24) | (12, 25) | (12, 26) | (12, 31)
+    ) || day == easter - Duration::days(2)
+        || day == easter + Duration::days(1);
+
+    !closed
+}
+
+// Gregorian computus (Meeus/Jones/Butcher)
+fn easter_sunday(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let h = (19 * a + b - b / 4 - (b - (b + 8) / 25 + 1) / 3 + 15) % 30;
+    let l = (32 + 2 * (b % 4) + 2 * (c / 4) - h - c % 4) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32).unwrap()
+}
+
+pub(super) fn parse_time_only(text: &str) -> Option<NaiveTime> {
+    ["%H.%M.%S", "%H:%M:%S"]
+        .into_iter()
+        .find_map(|fmt| NaiveTime::parse_from_str(text.trim(), fmt).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chrono::TimeZone;
+    use chrono_tz::Europe::Rome;
+
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn rome(y: i32, m: u32, d: u32, hour: u32, min: u32) -> DateTime<Utc> {
+        Rome.with_ymd_and_hms(y, m, d, hour, min, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn time(text: &str) -> Option<NaiveTime> {
+        parse_time_only(text)
+    }
+
+    // a trade of Monday 2 December 2024
+    fn raw(at: &str, price: &str, quantity: u64) -> RawTrade {
+        RawTrade {
+            traded_at: parse_trade_time(at, date(2024, 12, 2)).unwrap(),
+            price: Decimal::from_str(price).unwrap(),
+            quantity,
+        }
+    }
+
+    fn keys(trades: &[Trade]) -> Vec<(DateTime<Utc>, Decimal, u64, i32)> {
+        trades
+            .iter()
+            .map(|t| (t.traded_at, t.price, t.quantity, t.seq))
+            .collect()
+    }
+
+    #[test]
+    fn numbers_identical_trades() {
+        let trades = sequence_trades(
+            "IT0003128367",
+            vec![
+                raw("10.00.00", "6.868", 100),
+                raw("10.00.00", "6.868", 200),
+                raw("10.00.00", "6.868", 100),
+                raw("10.00.01", "6.868", 100),
+                raw("10.00.01", "6.868", 100),
+            ],
+        );
+
+        let seqs: Vec<i32> = trades.iter().map(|t| t.seq).collect();
+        assert_eq!(seqs, [0, 0, 1, 0, 1]);
+        assert!(trades.iter().all(|t| t.isin == "IT0003128367"));
+    }
+
+    // the insert skips the keys already in the table, so the trades seen by an
+    // earlier refresh must get the same seq again
+    #[test]
+    fn keeps_keys_across_refreshes() {
+        let first = sequence_trades(
+            "IT0003128367",
+            vec![
+                raw("10.00.00", "6.868", 100),
+                raw("10.00.05", "6.87", 50),
+                raw("10.00.05", "6.87", 50),
+            ],
+        );
+        let second = sequence_trades(
+            "IT0003128367",
+            vec![
+                raw("10.00.00", "6.868", 100),
+                raw("10.00.05", "6.87", 50),
+                raw("10.00.05", "6.87", 50),
+                // a third identical trade in the same second
+                raw("10.00.05", "6.87", 50),
+                raw("10.01.00", "6.866", 300),
+            ],
+        );
+
+        let (first, second) = (keys(&first), keys(&second));
+        assert!(first.iter().all(|key| second.contains(key)));
+        let new: Vec<_> = second.iter().filter(|key| !first.contains(key)).collect();
+        assert_eq!(new.len(), 2);
+        assert_eq!(new[0].3, 2);
+    }
+
+    #[test]
+    fn resolves_trading_day() {
+        let cases = [
+            // during the session
+            (
+                rome(2024, 12, 3, 10, 0),
+                time("09.59.00"),
+                date(2024, 12, 3),
+            ),
+            (rome(2024, 12, 3, 10, 0), None, date(2024, 12, 3)),
+            // after midnight the trades are of the day before
+            (
+                rome(2024, 12, 4, 0, 30),
+                time("17.35.12"),
+                date(2024, 12, 3),
+            ),
+            (
+                rome(2024, 12, 2, 0, 30),
+                time("17.35.12"),
+                date(2024, 11, 29),
+            ),
+            // weekends
+            (
+                rome(2024, 11, 30, 12, 0),
+                time("17.35.12"),
+                date(2024, 11, 29),
+            ),
+            (rome(2024, 12, 1, 12, 0), None, date(2024, 11, 29)),
+            // Easter, Good Friday is 18 April 2025
+            (
+                rome(2025, 4, 21, 10, 0),
+                time("17.35.12"),
+                date(2025, 4, 17),
+            ),
+            (
+                rome(2025, 4, 22, 0, 30),
+                time("17.35.12"),
+                date(2025, 4, 17),
+            ),
+            // Christmas
+            (
+                rome(2024, 12, 27, 0, 30),
+                time("17.35.12"),
+                date(2024, 12, 23),
+            ),
+            (
+                rome(2025, 1, 2, 0, 30),
+                time("17.35.12"),
+                date(2024, 12, 30),
+            ),
+            // Labour Day and Ferragosto
+            (rome(2025, 5, 2, 0, 30), time("17.35.12"), date(2025, 4, 30)),
+            (rome(2025, 8, 15, 12, 0), None, date(2025, 8, 14)),
+        ];
+
+        for (now, latest_time, expected) in cases {
+            assert_eq!(
+                resolve_trading_day(now, latest_time),
+                expected,
+                "{} with {:?}",
+                now.with_timezone(&Rome),
+                latest_time
+            );
+        }
+    }
+
+    #[test]
+    fn computes_easter() {
+        assert_eq!(easter_sunday(2024), date(2024, 3, 31));
+        assert_eq!(easter_sunday(2025), date(2025, 4, 20));
+        assert_eq!(easter_sunday(2026), date(2026, 4, 5));
+        assert_eq!(easter_sunday(2038), date(2038, 4, 25));
+    }
+}
@@ -0,0 +1,446 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, spanned::Spanned, Data, DeriveInput, Fields, GenericArgument, LitStr, Path,
+    PathArguments, Type,
+};
+
+// #[derive(Scrapable)] implements `ScrapableStruct` for a struct with `isin: String`,
+// `updated_at: DateTime<Utc>` and `Option<T>` fields annotated with
+//
+// #[scrape(label = "prezzo ultimo contratto")]
+//
+// the attribute takes one or more `label`s, `match_mode = "exact"`, a `value_selector`
+// css selector and a `parser` path to a `fn(&PropertyValue, &str) -> Result<T, ParseFailure>`
+// used instead of `TryParse<T>`
+//
+// the generated code refers to the scraper crate as `crate`, other crates name it
+// with #[scrape(crate = "scraper")] on the struct
+#[proc_macro_derive(Scrapable, attributes(scrape))]
+pub fn derive_scrapable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+struct ScrapedField {
+    ident: syn::Ident,
+    inner_type: Type,
+    labels: Vec<LitStr>,
+    exact: bool,
+    value_selector: Option<LitStr>,
+    parser: Option<Path>,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let krate = crate_path(&input)?;
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.span(),
+            "Scrapable only supports structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new(
+            input.span(),
+            "Scrapable only supports named fields",
+        ));
+    };
+
+    let mut scraped = Vec::new();
+    for field in fields.named.iter() {
+        let ident = field.ident.clone().unwrap();
+        let Some(attr) = field.attrs.iter().find(|a| a.path().is_ident("scrape")) else {
+            if ident != "isin" && ident != "updated_at" {
+                return Err(syn::Error::new(
+                    field.span(),
+                    "fields other than isin and updated_at need #[scrape(label = ...)]",
+                ));
+            }
+            continue;
+        };
+
+        let mut scraped_field = ScrapedField {
+            inner_type: option_inner_type(&field.ty)?,
+            ident,
+            labels: Vec::new(),
+            exact: false,
+            value_selector: None,
+            parser: None,
+        };
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("label") {
+                scraped_field.labels.push(meta.value()?.parse()?);
+            } else if meta.path.is_ident("match_mode") {
+                let mode: LitStr = meta.value()?.parse()?;
+                scraped_field.exact = match mode.value().as_str() {
+                    "exact" => true,
+                    "contains" => false,
+                    _ => return Err(meta.error("match_mode must be \"exact\" or \"contains\"")),
+                };
+            } else if meta.path.is_ident("value_selector") {
+                scraped_field.value_selector = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("parser") {
+                scraped_field.parser = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("unknown scrape attribute"));
+            }
+            Ok(())
+        })?;
+
+        if scraped_field.labels.is_empty() {
+            return Err(syn::Error::new(attr.span(), "missing label"));
+        }
+        scraped.push(scraped_field);
+    }
+
+    let default_mappings = scraped.iter().map(|field| {
+        let field_name = field.ident.to_string();
+        let labels = &field.labels;
+        let match_mode = if field.exact {
+            quote!(#krate::shares::mappings::MatchMode::Exact)
+        } else {
+            quote!(#krate::shares::mappings::MatchMode::Contains)
+        };
+        let value_selector = match &field.value_selector {
+            Some(selector) => quote!(Some(#selector)),
+            None => quote!(None),
+        };
+
+        quote! {
+            #krate::shares::mappings::DefaultMapping {
+                field: #field_name,
+                terms: &[#(#labels),*],
+                match_mode: #match_mode,
+                value_selector: #value_selector,
+            }
+        }
+    });
+
+    let parsed_fields = scraped.iter().map(|field| {
+        let ident = &field.ident;
+        let field_name = ident.to_string();
+        let inner_type = &field.inner_type;
+        let parse = match &field.parser {
+            Some(parser) => quote!(#parser(&el, #field_name)),
+            None => quote! {
+                #krate::shares::parsers::TryParse::<#inner_type>::try_parse_partial(
+                    &el,
+                    #field_name,
+                    failures,
+                )
+            },
+        };
+
+        quote! {
+            #ident: selector.get_property(#field_name).and_then(|el| {
+                let parsed: Result<#inner_type, #krate::shares::parsers::ParseFailure> = #parse;
+                match parsed {
+                    Ok(value) => Some(value),
+                    Err(failure) if failure.is_no_value() => {
+                        #krate::derive_support::debug!("{}", failure);
+                        None
+                    }
+                    Err(failure) => {
+                        #krate::derive_support::warn!("{}", failure);
+                        failures.push(failure);
+                        None
+                    }
+                }
+            }),
+        }
+    });
+
+    let empty_fields = scraped.iter().map(|field| {
+        let ident = &field.ident;
+        quote!(#ident: None,)
+    });
+
+    let name_str = name.to_string();
+    Ok(quote! {
+        impl #krate::shares::ScrapableStruct for #name {
+            fn default_mappings() -> Vec<#krate::shares::mappings::DefaultMapping> {
+                vec![#(#default_mappings),*]
+            }
+
+            fn from_selector(
+                share_isin: &#krate::isins::types::ShareIsin,
+                selector: &#krate::shares::property_selector::PropertySelector,
+                failures: &mut Vec<#krate::shares::parsers::ParseFailure>,
+            ) -> Self {
+                Self {
+                    isin: share_isin.isin.to_string(),
+                    updated_at: #krate::derive_support::Utc::now(),
+                    #(#parsed_fields)*
+                }
+            }
+
+            fn with_isin(share_isin: &#krate::isins::types::ShareIsin) -> Self {
+                #krate::derive_support::warn!("Creating empty {}", #name_str);
+                Self {
+                    isin: share_isin.isin.to_string(),
+                    updated_at: #krate::derive_support::Utc::now(),
+                    #(#empty_fields)*
+                }
+            }
+        }
+    })
+}
+
+// the path of #[scrape(crate = "...")] on the struct, `crate` by default
+fn crate_path(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let mut krate = quote!(crate);
+
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("scrape")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("crate") {
+                let path: LitStr = meta.value()?.parse()?;
+                let path: Path = path.parse()?;
+                krate = quote!(#path);
+                Ok(())
+            } else {
+                Err(meta.error("unknown scrape attribute, the struct only takes `crate`"))
+            }
+        })?;
+    }
+
+    Ok(krate)
+}
+
+// T of an Option<T> field
+fn option_inner_type(ty: &Type) -> syn::Result<Type> {
+    if let Type::Path(type_path) = ty {
+        if let Some(segment) = type_path.path.segments.last() {
+            if segment.ident == "Option" {
+                if let PathArguments::AngleBracketed(args) = &segment.arguments {
+                    if let Some(GenericArgument::Type(inner)) = args.args.first() {
+                        return Ok(inner.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    Err(syn::Error::new(
+        ty.span(),
+        "scraped fields must be Option<T>",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn expanded(input: DeriveInput) -> String {
+        expand(input).unwrap().to_string()
+    }
+
+    fn error(input: DeriveInput) -> String {
+        expand(input).unwrap_err().to_string()
+    }
+
+    // compared as token strings, the spacing of both is the same
+    fn assert_expands_to(expanded: &str, expected: TokenStream2) {
+        let expected = expected.to_string();
+        assert!(
+            expanded.contains(&expected),
+            "{}\nisn't part of\n{}",
+            expected,
+            expanded
+        );
+    }
+
+    #[test]
+    fn maps_the_labels() {
+        let expanded = expanded(parse_quote! {
+            struct Details {
+                isin: String,
+                #[scrape(label = "id strumento", label = "codice strumento")]
+                id_strumento: Option<f64>,
+                updated_at: DateTime<Utc>,
+            }
+        });
+
+        assert_expands_to(
+            &expanded,
+            quote! {
+                crate::shares::mappings::DefaultMapping {
+                    field: "id_strumento",
+                    terms: &["id strumento", "codice strumento"],
+                    match_mode: crate::shares::mappings::MatchMode::Contains,
+                    value_selector: None,
+                }
+            },
+        );
+        assert_expands_to(
+            &expanded,
+            quote! {
+                crate::shares::parsers::TryParse::<f64>::try_parse_partial(
+                    &el,
+                    "id_strumento",
+                    failures,
+                )
+            },
+        );
+        assert_expands_to(&expanded, quote!(id_strumento: None,));
+    }
+
+    #[test]
+    fn sets_match_mode_and_value_selector() {
+        let expanded = expanded(parse_quote! {
+            struct Profile {
+                isin: String,
+                #[scrape(label = "sito web", match_mode = "exact", value_selector = "a")]
+                sito_web: Option<String>,
+                #[scrape(label = "flottante", match_mode = "contains")]
+                flottante: Option<Decimal>,
+                updated_at: DateTime<Utc>,
+            }
+        });
+
+        assert_expands_to(
+            &expanded,
+            quote! {
+                match_mode: crate::shares::mappings::MatchMode::Exact,
+                value_selector: Some("a"),
+            },
+        );
+        assert_expands_to(
+            &expanded,
+            quote! {
+                field: "flottante",
+                terms: &["flottante"],
+                match_mode: crate::shares::mappings::MatchMode::Contains,
+                value_selector: None,
+            },
+        );
+    }
+
+    #[test]
+    fn calls_the_parser() {
+        let expanded = expanded(parse_quote! {
+            struct Index {
+                isin: String,
+                #[scrape(label = "valore", parser = parsers::parse_level)]
+                valore: Option<Decimal>,
+                updated_at: DateTime<Utc>,
+            }
+        });
+
+        assert_expands_to(&expanded, quote!(parsers::parse_level(&el, "valore")));
+        assert!(!expanded.contains("TryParse"), "{}", expanded);
+    }
+
+    #[test]
+    fn uses_the_crate_path() {
+        let expanded = expanded(parse_quote! {
+            #[scrape(crate = "::share_scraper")]
+            struct Details {
+                isin: String,
+                #[scrape(label = "id strumento")]
+                id_strumento: Option<f64>,
+                updated_at: DateTime<Utc>,
+            }
+        });
+
+        assert_expands_to(
+            &expanded,
+            quote!(impl ::share_scraper::shares::ScrapableStruct for Details),
+        );
+        assert_expands_to(
+            &expanded,
+            quote!(updated_at: ::share_scraper::derive_support::Utc::now(),),
+        );
+        assert!(!expanded.contains("crate ::"), "{}", expanded);
+    }
+
+    #[test]
+    fn rejects_invalid_attributes() {
+        let cases: [(DeriveInput, &str); 6] = [
+            (
+                parse_quote! {
+                    struct S { #[scrape(lable = "x")] a: Option<f64> }
+                },
+                "unknown scrape attribute",
+            ),
+            (
+                parse_quote! {
+                    struct S { #[scrape(label = "x", match_mode = "prefix")] a: Option<f64> }
+                },
+                "match_mode must be \"exact\" or \"contains\"",
+            ),
+            (
+                parse_quote! {
+                    struct S { #[scrape(match_mode = "exact")] a: Option<f64> }
+                },
+                "missing label",
+            ),
+            (
+                parse_quote! {
+                    struct S { #[scrape(label = 3)] a: Option<f64> }
+                },
+                "expected string literal",
+            ),
+            (
+                parse_quote! {
+                    struct S { #[scrape(label = "x", parser = "parse")] a: Option<f64> }
+                },
+                "expected identifier",
+            ),
+            (
+                parse_quote! {
+                    #[scrape(label = "x")]
+                    struct S { isin: String }
+                },
+                "unknown scrape attribute, the struct only takes `crate`",
+            ),
+        ];
+
+        for (input, expected) in cases {
+            let error = error(input);
+            assert!(error.starts_with(expected), "{:?}", error);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_structs() {
+        let cases: [(DeriveInput, &str); 4] = [
+            (
+                parse_quote! {
+                    struct S { #[scrape(label = "x")] a: f64 }
+                },
+                "scraped fields must be Option<T>",
+            ),
+            (
+                parse_quote! {
+                    struct S { isin: String, a: Option<f64> }
+                },
+                "fields other than isin and updated_at need #[scrape(label = ...)]",
+            ),
+            (
+                parse_quote! {
+                    struct S(Option<f64>);
+                },
+                "Scrapable only supports named fields",
+            ),
+            (
+                parse_quote! {
+                    enum S { A }
+                },
+                "Scrapable only supports structs",
+            ),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(error(input), expected);
+        }
+    }
+}
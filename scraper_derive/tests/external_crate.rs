@@ -0,0 +1,54 @@
+// the derive used outside the scraper crate, which names itself with `crate`
+
+use chrono::{DateTime, Utc};
+use scraper::{
+    isins::types::ShareIsin,
+    shares::{
+        mappings::MatchMode,
+        parsers::{ParseFailure, TryParse},
+        property_selector::PropertyValue,
+        ScrapableStruct,
+    },
+};
+use scraper_derive::Scrapable;
+
+#[derive(Debug, Scrapable)]
+#[scrape(crate = "scraper")]
+struct Quote {
+    isin: String,
+    #[scrape(label = "ultimo prezzo", label = "prezzo", match_mode = "exact")]
+    prezzo: Option<f64>,
+    #[scrape(label = "fase", value_selector = "span.phase", parser = parse_phase)]
+    fase: Option<String>,
+    updated_at: DateTime<Utc>,
+}
+
+fn parse_phase(value: &PropertyValue, field: &str) -> Result<String, ParseFailure> {
+    TryParse::<String>::try_parse(value, field).map(|phase| phase.to_uppercase())
+}
+
+#[test]
+fn declares_the_mappings() {
+    let mappings = Quote::default_mappings();
+
+    assert_eq!(Quote::fields(), ["prezzo", "fase"]);
+    assert_eq!(mappings[0].terms, ["ultimo prezzo", "prezzo"]);
+    assert_eq!(mappings[0].match_mode, MatchMode::Exact);
+    assert_eq!(mappings[0].value_selector, None);
+    assert_eq!(mappings[1].match_mode, MatchMode::Contains);
+    assert_eq!(mappings[1].value_selector, Some("span.phase"));
+}
+
+#[test]
+fn creates_empty_structs() {
+    let share_isin = ShareIsin::new("ENEL".to_owned(), "IT0003128367".to_owned()).unwrap();
+    let quote = Quote::with_isin(&share_isin);
+
+    assert_eq!(quote.isin, "IT0003128367");
+    assert_eq!((quote.prezzo, quote.fase), (None, None));
+    assert!(quote.updated_at <= Utc::now());
+    assert_eq!(
+        parse_phase(&PropertyValue::Text(Some("asta")), "fase").unwrap(),
+        "ASTA"
+    );
+}
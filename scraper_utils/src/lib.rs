@@ -1,13 +1,32 @@
-use chrono::{Duration, NaiveTime, Utc};
+use chrono::{Duration, NaiveDate, NaiveTime, Timelike, Utc};
+use chrono_tz::Europe::Rome;
 use db::{
+    daily_bars::build_all_daily_bars,
+    dividends::insert_all_dividends,
+    indices::insert_all_indices,
     isins::{insert_all_isins, query_all_isins},
+    maintenance::{maintain_snapshots, MaintenanceReport, RetentionPolicy},
     metrics::InsertionMetrics,
+    press_releases::{insert_all_press_releases, query_stored_release_links},
+    profiles::{get_profiles_to_refresh, insert_all_profiles},
     shares::{get_shares_to_refresh, insert_all_shares},
+    trades::insert_all_trades,
 };
 use scraper::{
-    get_elapsed_time, isins::scrape_all_isins, metrics::ScrapingMetrics, shares::scrape_all_shares,
+    dividends::scrape_all_dividends,
+    get_elapsed_time,
+    indices::scrape_all_indices,
+    isins::scrape_all_isins,
+    metrics::ScrapingMetrics,
+    press_releases::scrape_all_press_releases,
+    shares::{profiles::scrape_all_profiles, scrape_all_shares},
+    trades::scrape_all_trades,
 };
-use tracing::{info, info_span, instrument, Instrument};
+use sqlx::{Pool, Postgres};
+use tracing::{error, info, info_span, instrument, Instrument};
+
+// the official prices are published after the closing auction
+const DAILY_BARS_FINALIZE_HOUR: u32 = 18;
 
 #[derive(Debug)]
 pub struct ScrapeAndInsertInfo {
@@ -39,29 +58,86 @@ where
     }
 }
 
-pub async fn run_scrape_and_insert() -> ScrapeAndInsertInfo {
-    run_timed(scrape_and_insert_all_shares).await
+// `batch_size` is DbConfig::shares_batch_size
+pub async fn run_scrape_and_insert(
+    batch_size: usize,
+    pool: &Pool<Postgres>,
+) -> ScrapeAndInsertInfo {
+    run_timed(|| scrape_and_insert_all_shares(batch_size, pool)).await
+}
+
+pub async fn run_share_refresh(batch_size: usize, pool: &Pool<Postgres>) -> ScrapeAndInsertInfo {
+    run_timed(|| refresh_shares(Duration::minutes(15), batch_size, pool)).await
+}
+
+// profiles rarely change, unlike prices
+pub async fn run_profile_refresh(pool: &Pool<Postgres>) -> ScrapeAndInsertInfo {
+    run_timed(|| refresh_profiles(Duration::days(7), pool)).await
+}
+
+pub async fn run_scrape_and_insert_isins(pool: &Pool<Postgres>) -> ScrapeAndInsertInfo {
+    run_timed(|| scrape_and_insert_all_isins(pool)).await
+}
+
+pub async fn run_scrape_and_insert_trades(pool: &Pool<Postgres>) -> ScrapeAndInsertInfo {
+    run_timed(|| scrape_and_insert_all_trades(pool)).await
+}
+
+pub async fn run_scrape_and_insert_indices(pool: &Pool<Postgres>) -> ScrapeAndInsertInfo {
+    run_timed(|| scrape_and_insert_all_indices(pool)).await
 }
 
-pub async fn run_share_refresh() -> ScrapeAndInsertInfo {
-    run_timed(|| async move { refresh_shares(Duration::minutes(15)).await }).await
+pub async fn run_scrape_and_insert_dividends(pool: &Pool<Postgres>) -> ScrapeAndInsertInfo {
+    run_timed(|| scrape_and_insert_all_dividends(pool)).await
 }
 
-pub async fn run_scrape_and_insert_isins() -> ScrapeAndInsertInfo {
-    run_timed(scrape_and_insert_all_isins).await
+pub async fn run_scrape_and_insert_press_releases(pool: &Pool<Postgres>) -> ScrapeAndInsertInfo {
+    run_timed(|| scrape_and_insert_all_press_releases(pool)).await
 }
 
-#[instrument]
-pub async fn refresh_shares(before: Duration) -> ScrapeAndInsertMetrics {
+pub async fn run_daily_bars_finalize(pool: &Pool<Postgres>) -> ScrapeAndInsertInfo {
+    run_timed(|| finalize_daily_bars(pool)).await
+}
+
+// partitions the snapshots and applies the retention policy, nothing is scraped
+pub async fn run_snapshot_maintenance(
+    policy: RetentionPolicy,
+    pool: &Pool<Postgres>,
+) -> Option<MaintenanceReport> {
+    let start_time = Utc::now().time();
+    let report = maintain_snapshots(&policy, pool)
+        .instrument(info_span!("maintain_snapshots"))
+        .await
+        .inspect_err(|e| error!("Snapshot maintenance failed: {}", e))
+        .ok();
+
+    info!("Total Time elapsed {}ms", get_elapsed_time(start_time));
+    report
+}
+
+// both days are included
+pub async fn run_daily_bars_rebuild(
+    from: NaiveDate,
+    to: NaiveDate,
+    pool: &Pool<Postgres>,
+) -> ScrapeAndInsertInfo {
+    run_timed(|| rebuild_daily_bars(from, to, pool)).await
+}
+
+#[instrument(skip(pool))]
+pub async fn refresh_shares(
+    before: Duration,
+    batch_size: usize,
+    pool: &Pool<Postgres>,
+) -> ScrapeAndInsertMetrics {
     info!("Refreshing all shares not updated in {:?}", before);
 
-    let pool = db::connect().await.unwrap();
-    let share_isins = get_shares_to_refresh(&pool, before)
+    let share_isins = get_shares_to_refresh(pool, before)
         .await
         .expect("Failed to query shares to scrape");
 
     let mut shares = scrape_all_shares(share_isins).await;
-    let insertion_metrics = insert_all_shares(shares.unmetric(), &pool).await;
+    let insertion_metrics = insert_all_shares(shares.unmetric(), batch_size, pool).await;
 
     ScrapeAndInsertMetrics {
         scrape: shares.metrics,
@@ -69,17 +145,39 @@ pub async fn refresh_shares(before: Duration) -> ScrapeAndInsertMetrics {
     }
 }
 
-#[instrument]
-pub async fn scrape_and_insert_all_shares() -> ScrapeAndInsertMetrics {
+#[instrument(skip(pool))]
+pub async fn refresh_profiles(before: Duration, pool: &Pool<Postgres>) -> ScrapeAndInsertMetrics {
+    info!(
+        "Refreshing all company profiles not updated in {:?}",
+        before
+    );
+
+    let share_isins = get_profiles_to_refresh(pool, before)
+        .await
+        .expect("Failed to query profiles to scrape");
+
+    let mut profiles = scrape_all_profiles(share_isins).await;
+    let insertion_metrics = insert_all_profiles(profiles.unmetric(), pool).await;
+
+    ScrapeAndInsertMetrics {
+        scrape: profiles.metrics,
+        insert: insertion_metrics,
+    }
+}
+
+#[instrument(skip(pool))]
+pub async fn scrape_and_insert_all_shares(
+    batch_size: usize,
+    pool: &Pool<Postgres>,
+) -> ScrapeAndInsertMetrics {
     info!("Started scraping and inserting all shares");
 
-    let pool = db::connect().await.unwrap();
-    let share_isins = query_all_isins(&pool)
+    let share_isins = query_all_isins(pool)
         .await
         .expect("Failed to query all ISINs");
 
     let mut shares = scrape_all_shares(share_isins).await;
-    let insertion_metrics = insert_all_shares(shares.unmetric(), &pool).await;
+    let insertion_metrics = insert_all_shares(shares.unmetric(), batch_size, pool).await;
 
     ScrapeAndInsertMetrics {
         scrape: shares.metrics,
@@ -87,13 +185,12 @@ pub async fn scrape_and_insert_all_shares() -> ScrapeAndInsertMetrics {
     }
 }
 
-#[instrument]
-pub async fn scrape_and_insert_all_isins() -> ScrapeAndInsertMetrics {
+#[instrument(skip(pool))]
+pub async fn scrape_and_insert_all_isins(pool: &Pool<Postgres>) -> ScrapeAndInsertMetrics {
     info!("Started scraping and inserting all isins");
 
     let mut isins = scrape_all_isins().await;
-    let pool = db::connect().await.unwrap();
-    let insertion_metrics = insert_all_isins(isins.unmetric().into_iter().collect(), &pool)
+    let insertion_metrics = insert_all_isins(isins.unmetric().into_iter().collect(), pool)
         .instrument(info_span!("insert_all_isins"))
         .await;
 
@@ -102,3 +199,111 @@ pub async fn scrape_and_insert_all_isins() -> ScrapeAndInsertMetrics {
         insert: insertion_metrics,
     }
 }
+
+#[instrument(skip(pool))]
+pub async fn scrape_and_insert_all_trades(pool: &Pool<Postgres>) -> ScrapeAndInsertMetrics {
+    info!("Started scraping and inserting all trades");
+
+    let share_isins = query_all_isins(pool)
+        .await
+        .expect("Failed to query all ISINs");
+
+    let mut trades = scrape_all_trades(share_isins).await;
+    let insertion_metrics = insert_all_trades(trades.unmetric(), pool)
+        .instrument(info_span!("insert_all_trades"))
+        .await;
+
+    ScrapeAndInsertMetrics {
+        scrape: trades.metrics,
+        insert: insertion_metrics,
+    }
+}
+
+#[instrument(skip(pool))]
+pub async fn scrape_and_insert_all_dividends(pool: &Pool<Postgres>) -> ScrapeAndInsertMetrics {
+    info!("Started scraping and inserting all dividends");
+
+    let share_isins = query_all_isins(pool)
+        .await
+        .expect("Failed to query all ISINs");
+
+    let mut dividends = scrape_all_dividends(share_isins).await;
+    let insertion_metrics = insert_all_dividends(dividends.unmetric(), pool)
+        .instrument(info_span!("insert_all_dividends"))
+        .await;
+
+    ScrapeAndInsertMetrics {
+        scrape: dividends.metrics,
+        insert: insertion_metrics,
+    }
+}
+
+#[instrument(skip(pool))]
+pub async fn scrape_and_insert_all_press_releases(pool: &Pool<Postgres>) -> ScrapeAndInsertMetrics {
+    info!("Started scraping and inserting all press releases");
+
+    let share_isins = query_all_isins(pool)
+        .await
+        .expect("Failed to query all ISINs");
+
+    let stored_links = query_stored_release_links(pool)
+        .await
+        .expect("Failed to query the stored press releases");
+
+    let mut releases = scrape_all_press_releases(share_isins, &stored_links).await;
+    let insertion_metrics = insert_all_press_releases(releases.unmetric(), pool)
+        .instrument(info_span!("insert_all_press_releases"))
+        .await;
+
+    ScrapeAndInsertMetrics {
+        scrape: releases.metrics,
+        insert: insertion_metrics,
+    }
+}
+
+#[instrument(skip(pool))]
+pub async fn scrape_and_insert_all_indices(pool: &Pool<Postgres>) -> ScrapeAndInsertMetrics {
+    info!("Started scraping and inserting all indices");
+
+    let mut indices = scrape_all_indices().await;
+    let insertion_metrics = insert_all_indices(indices.unmetric(), pool)
+        .instrument(info_span!("insert_all_indices"))
+        .await;
+
+    ScrapeAndInsertMetrics {
+        scrape: indices.metrics,
+        insert: insertion_metrics,
+    }
+}
+
+// builds the bars of the last closed trading day, today's once the official
+// prices are out, yesterday's before that
+#[instrument(skip(pool))]
+pub async fn finalize_daily_bars(pool: &Pool<Postgres>) -> ScrapeAndInsertMetrics {
+    let now = Utc::now().with_timezone(&Rome);
+    let trade_date = if now.hour() >= DAILY_BARS_FINALIZE_HOUR {
+        now.date_naive()
+    } else {
+        now.date_naive().pred_opt().unwrap()
+    };
+    info!("Finalizing daily bars of {}", trade_date);
+
+    rebuild_daily_bars(trade_date, trade_date, pool).await
+}
+
+#[instrument(skip(pool))]
+pub async fn rebuild_daily_bars(
+    from: NaiveDate,
+    to: NaiveDate,
+    pool: &Pool<Postgres>,
+) -> ScrapeAndInsertMetrics {
+    let insertion_metrics = build_all_daily_bars(from, to, pool)
+        .instrument(info_span!("build_all_daily_bars"))
+        .await;
+
+    // bars are built from the stored snapshots, nothing is scraped
+    ScrapeAndInsertMetrics {
+        scrape: ScrapingMetrics::empty(),
+        insert: insertion_metrics,
+    }
+}
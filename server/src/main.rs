@@ -3,9 +3,19 @@ use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::Json;
 use axum::{extract::State, routing::get, Router};
+use db::daily_bars::{query_daily_bars, DailyBarQuery};
+use db::dividends::{query_dividend_history, query_upcoming_dividends};
+use db::field_changes::{query_change_timeline, ChangeTimelineQuery};
+use db::indices::{query_all_indices, query_index_memberships};
 use db::isins::query_all_isins;
+use db::order_books::query_latest_order_book;
+use db::press_releases::{search_press_releases, PressReleaseQuery};
+use db::price_snapshots::{query_price_snapshots, PriceHistoryQuery};
+use db::profiles::query_company_profile;
 use db::shares::{query_share_with, ShareQuery};
-use scraper::shares::Share;
+use rust_decimal::Decimal;
+use scraper::shares::{OrderBook, Share};
+use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use std::sync::Arc;
 use std::{net::SocketAddr, sync::Mutex};
@@ -17,6 +27,23 @@ struct AppState {
     db: PgPool,
 }
 
+#[derive(Deserialize)]
+struct IsinQuery {
+    isin: String,
+}
+
+#[derive(Deserialize)]
+struct IndexQuery {
+    code: String,
+}
+
+#[derive(Serialize)]
+struct OrderBookResponse {
+    #[serde(flatten)]
+    order_book: OrderBook,
+    spread: Option<Decimal>,
+}
+
 #[tokio::main]
 async fn main() {
     let log_file = std::fs::File::create("../server.log").expect("Can't create log file");
@@ -48,6 +75,16 @@ async fn main() {
         .route("/all_isins", get(all_isins))
         .route("/all_shares", get(all_shares))
         .route("/share", get(query_share))
+        .route("/order_book", get(order_book))
+        .route("/company_profile", get(company_profile))
+        .route("/dividends", get(dividends))
+        .route("/upcoming_dividends", get(upcoming_dividends))
+        .route("/press_releases", get(press_releases))
+        .route("/price_history", get(price_history))
+        .route("/daily_bars", get(daily_bars))
+        .route("/change_timeline", get(change_timeline))
+        .route("/indices", get(indices))
+        .route("/index_memberships", get(index_memberships))
         .with_state(shared_state);
 
     let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
@@ -91,3 +128,136 @@ async fn query_share(
         }
     }
 }
+
+async fn order_book(
+    Query(query): Query<IsinQuery>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    match query_latest_order_book(&query.isin, &state.db).await {
+        Ok(Some(order_book)) => Json(OrderBookResponse {
+            spread: order_book.spread(),
+            order_book,
+        })
+        .into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(db_err) => {
+            error!("DB error: {}", db_err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn company_profile(
+    Query(query): Query<IsinQuery>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    match query_company_profile(&query.isin, &state.db).await {
+        Ok(Some(profile)) => Json(profile).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(db_err) => {
+            error!("DB error: {}", db_err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn dividends(
+    Query(query): Query<IsinQuery>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    match query_dividend_history(&query.isin, &state.db).await {
+        Ok(dividends) => Json(dividends).into_response(),
+        Err(db_err) => {
+            error!("DB error: {}", db_err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn upcoming_dividends(
+    Query(query): Query<IsinQuery>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    match query_upcoming_dividends(&query.isin, &state.db).await {
+        Ok(dividends) => Json(dividends).into_response(),
+        Err(db_err) => {
+            error!("DB error: {}", db_err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn press_releases(
+    Query(query): Query<PressReleaseQuery>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    match search_press_releases(query, &state.db).await {
+        Ok(releases) => Json(releases).into_response(),
+        Err(db_err) => {
+            error!("DB error: {}", db_err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn price_history(
+    Query(query): Query<PriceHistoryQuery>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    match query_price_snapshots(&query, &state.db).await {
+        Ok(snapshots) => Json(snapshots).into_response(),
+        Err(db_err) => {
+            error!("DB error: {}", db_err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn daily_bars(
+    Query(query): Query<DailyBarQuery>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    match query_daily_bars(&query, &state.db).await {
+        Ok(bars) => Json(bars).into_response(),
+        Err(db_err) => {
+            error!("DB error: {}", db_err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn change_timeline(
+    Query(query): Query<ChangeTimelineQuery>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    match query_change_timeline(query, &state.db).await {
+        Ok(changes) => Json(changes).into_response(),
+        Err(db_err) => {
+            error!("DB error: {}", db_err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn indices(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match query_all_indices(&state.db).await {
+        Ok(indices) => Json(indices).into_response(),
+        Err(db_err) => {
+            error!("DB error: {}", db_err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn index_memberships(
+    Query(query): Query<IndexQuery>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    match query_index_memberships(&query.code, &state.db).await {
+        Ok(memberships) => Json(memberships).into_response(),
+        Err(db_err) => {
+            error!("DB error: {}", db_err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -1,23 +1,284 @@
-use std::sync::Mutex;
+use std::{path::PathBuf, sync::Mutex};
 
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
 // use scraper_utils::run_scrape_and_insert_isins;
-use scraper_utils::run_share_refresh;
+use db::{
+    maintenance::RetentionPolicy,
+    migrations::{migration_status, pending_migrations, run_migrations},
+    DbConfig,
+};
+use scraper::{
+    isins::{self, types::ShareIsin},
+    quarantine::init_quarantine,
+    shares::{self, mappings::init_mappings, outcomes::share_field_outcomes},
+};
+use scraper_utils::{
+    run_daily_bars_finalize, run_daily_bars_rebuild, run_profile_refresh,
+    run_scrape_and_insert_dividends, run_scrape_and_insert_indices,
+    run_scrape_and_insert_press_releases, run_scrape_and_insert_trades, run_share_refresh,
+    run_snapshot_maintenance,
+};
+use serde_json::json;
+use sqlx::{Pool, Postgres};
+use tracing::info;
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
+#[derive(Parser)]
+#[command(about = "Scrapes shares from borsaitaliana.it")]
+struct Cli {
+    // refreshes the shares when omitted
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Refresh shares not updated in the last 15 minutes")]
+    Refresh,
+    #[command(about = "Refresh company profiles not updated in the last 7 days")]
+    Profiles,
+    #[command(about = "Add the latest trades of every share to the trade tape")]
+    Trades,
+    #[command(about = "Scrape the dividend history and announced dividends of every share")]
+    Dividends,
+    #[command(about = "Ingest the press releases of every share for the full-text search")]
+    PressReleases,
+    #[command(about = "Scrape the index levels and update the index memberships")]
+    Indices,
+    #[command(about = "Build the daily bars of the last closed trading day")]
+    DailyBars {
+        #[arg(
+            long,
+            requires = "to",
+            help = "Rebuild the bars from this day (YYYY-MM-DD) instead"
+        )]
+        from: Option<NaiveDate>,
+        #[arg(long, requires = "from", help = "Last day to rebuild, included")]
+        to: Option<NaiveDate>,
+    },
+    #[command(about = "Create upcoming snapshot partitions and apply the retention policy")]
+    Maintain {
+        #[arg(long, help = "Keep only the last snapshot of each day older than this")]
+        downsample_after_days: Option<i32>,
+        #[arg(long, help = "Drop the snapshot partitions older than this")]
+        drop_after_months: Option<i32>,
+    },
+    #[command(about = "Apply the pending database migrations")]
+    Migrate {
+        #[arg(long, help = "List the applied and pending migrations instead")]
+        status: bool,
+        #[arg(
+            long,
+            conflicts_with = "status",
+            help = "List the migrations that would be applied without applying them"
+        )]
+        dry_run: bool,
+    },
+    #[command(about = "Parse saved HTML pages offline and print the result as JSON")]
+    Parse {
+        #[arg(long, help = "The files are A-Z listing pages instead of share pages")]
+        listing: bool,
+        #[arg(
+            long,
+            help = "ISIN of the share pages, the file name is used by default"
+        )]
+        isin: Option<String>,
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+}
+
 #[tokio::main]
 async fn main() {
+    let cli = Cli::parse();
+
+    // the parse output goes to stdout, logs would mix with it
+    let console_logs = !matches!(cli.command, Some(Command::Parse { .. }));
+    init_logging(console_logs);
+
+    // overrides of the default label mappings, see scraper/mappings.example.toml
+    let mappings_path = std::env::var_os("SCRAPER_MAPPINGS").map(PathBuf::from);
+    init_mappings(mappings_path.as_deref()).expect("Invalid field mappings");
+    if let Some(dir) = std::env::var_os("SCRAPER_QUARANTINE_DIR") {
+        init_quarantine(PathBuf::from(dir));
+    }
+
+    match cli.command.unwrap_or(Command::Refresh) {
+        Command::Refresh => {
+            let config = db_config();
+            let pool = connect_db(&config).await;
+            log_result(
+                "Share refresh",
+                run_share_refresh(config.shares_batch_size, &pool).await,
+            );
+        }
+        Command::Profiles => {
+            let pool = connect_db(&db_config()).await;
+            log_result("Profile refresh", run_profile_refresh(&pool).await);
+        }
+        Command::Trades => {
+            let pool = connect_db(&db_config()).await;
+            log_result("Trades", run_scrape_and_insert_trades(&pool).await);
+        }
+        Command::Dividends => {
+            let pool = connect_db(&db_config()).await;
+            log_result("Dividends", run_scrape_and_insert_dividends(&pool).await);
+        }
+        Command::PressReleases => {
+            let pool = connect_db(&db_config()).await;
+            log_result(
+                "Press releases",
+                run_scrape_and_insert_press_releases(&pool).await,
+            );
+        }
+        Command::Indices => {
+            let pool = connect_db(&db_config()).await;
+            log_result("Indices", run_scrape_and_insert_indices(&pool).await);
+        }
+        Command::DailyBars { from, to } => {
+            let pool = connect_db(&db_config()).await;
+            match from.zip(to) {
+                Some((from, to)) => {
+                    log_result(
+                        "Daily bars rebuild",
+                        run_daily_bars_rebuild(from, to, &pool).await,
+                    );
+                }
+                None => {
+                    log_result("Daily bars", run_daily_bars_finalize(&pool).await);
+                }
+            }
+        }
+        Command::Maintain {
+            downsample_after_days,
+            drop_after_months,
+        } => {
+            let default = RetentionPolicy::default();
+            let policy = RetentionPolicy {
+                downsample_after_days: downsample_after_days
+                    .unwrap_or(default.downsample_after_days),
+                drop_after_months: drop_after_months.unwrap_or(default.drop_after_months),
+                ..default
+            };
+            log_result(
+                "Snapshot maintenance",
+                run_snapshot_maintenance(policy, &connect_db(&db_config()).await).await,
+            );
+        }
+        Command::Migrate { status, dry_run } => {
+            migrate(status, dry_run, &connect_db(&db_config()).await).await
+        }
+        Command::Parse {
+            listing,
+            isin,
+            files,
+        } => {
+            let mut results = Vec::new();
+            for file in files {
+                let res_txt = std::fs::read_to_string(&file)
+                    .unwrap_or_else(|e| panic!("Can't read {}: {}", file.display(), e));
+
+                let result = if listing {
+                    json!(isins::parse_page(res_txt))
+                } else {
+                    parse_share_file(res_txt, &file, isin.as_deref()).await
+                };
+                results.push(json!({ "file": file, "result": result }));
+            }
+
+            println!("{}", serde_json::to_string_pretty(&results).unwrap());
+        }
+    }
+}
+
+// the metrics of a scrape or maintenance run, on stdout and in the log file
+fn log_result(operation: &str, result: impl std::fmt::Debug) {
+    info!("{} finished: {:?}", operation, result);
+}
+
+fn db_config() -> DbConfig {
+    DbConfig::from_env().expect("Invalid database config")
+}
+
+// one pool for the whole command
+async fn connect_db(config: &DbConfig) -> Pool<Postgres> {
+    config
+        .connect()
+        .await
+        .expect("Can't connect to the database")
+}
+
+async fn migrate(status: bool, dry_run: bool, pool: &Pool<Postgres>) {
+    if status {
+        let migrations = migration_status(pool)
+            .await
+            .expect("Can't read the migration status");
+        for migration in migrations {
+            let state = match migration.installed_on {
+                Some(installed_on) if migration.unknown => {
+                    format!("applied {}, not in migrations/", installed_on)
+                }
+                Some(_) if migration.checksum_mismatch => "changed after being applied".to_owned(),
+                Some(installed_on) => format!("applied {}", installed_on),
+                None => "pending".to_owned(),
+            };
+            println!("{} {}: {}", migration.version, migration.description, state);
+        }
+        return;
+    }
+
+    let migrations = if dry_run {
+        pending_migrations(pool)
+            .await
+            .expect("Can't read the migration status")
+    } else {
+        run_migrations(pool).await.expect("Migration failed")
+    };
+
+    if migrations.is_empty() {
+        println!("No pending migrations");
+    }
+    for migration in migrations {
+        let action = if dry_run { "Would apply" } else { "Applied" };
+        println!("{} {} {}", action, migration.version, migration.description);
+    }
+}
+
+async fn parse_share_file(
+    res_txt: String,
+    file: &std::path::Path,
+    isin: Option<&str>,
+) -> serde_json::Value {
+    // e.g. IT0003128367.html
+    let isin = isin
+        .map(str::to_owned)
+        .or_else(|| Some(file.file_stem()?.to_string_lossy().to_uppercase()))
+        .unwrap_or_default();
+    let Some(share_isin) = ShareIsin::new(isin.clone(), isin.clone()) else {
+        return json!({ "error": format!("{:?} isn't a valid ISIN, pass it with --isin", isin) });
+    };
+
+    let (share, failures) = match shares::parse_page(res_txt.clone(), &share_isin).await {
+        Ok(res) => res,
+        Err(e) => return json!({ "error": format!("{:?}", e) }),
+    };
+    let fields = share_field_outcomes(&res_txt, &failures);
+
+    json!({ "share": share, "fields": fields })
+}
+
+fn init_logging(console: bool) {
     let log_file = std::fs::File::create("share_scraper.log").expect("Can't create log file");
 
     let file_logger = fmt::layer()
         .with_writer(Mutex::new(log_file))
         .with_ansi(false);
-    let stdout_logger = fmt::layer().with_ansi(true);
+    let stdout_logger = console.then(|| fmt::layer().with_ansi(true));
 
     tracing_subscriber::registry()
         .with(EnvFilter::from_default_env())
         .with(file_logger)
         .with(stdout_logger)
         .init();
-
-    dbg!(run_share_refresh().await);
 }